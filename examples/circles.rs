@@ -128,8 +128,7 @@ fn main() {
             {
                 let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
-                pass.set_pipeline(&pip);
-                pass.draw_buffer(&buffer);
+                pass.set_pipeline(&pip).draw_buffer(&buffer);
             }
             r.submit(frame);
         }
@@ -87,8 +87,7 @@ fn main() {
             {
                 let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &output);
 
-                pass.set_pipeline(&pipeline);
-                pass.draw(&buffer, &binding);
+                pass.set_pipeline(&pipeline).draw(&buffer, &binding);
             }
             renderer.submit(frame);
         }
@@ -18,21 +18,29 @@ use winit::{
     window::Window,
 };
 
+rgx::vertex! {
+    #[derive(Copy, Clone)]
+    pub struct Vertex {
+        position: [f32; 2],
+        uv: [f32; 2],
+    }
+}
+
 pub struct Framebuffer {
     target: core::Framebuffer,
-    vertices: core::VertexBuffer,
+    vertices: core::VertexBuffer<Vertex>,
 }
 
 impl Framebuffer {
     fn new(w: u32, h: u32, r: &core::Renderer) -> Self {
         #[rustfmt::skip]
-        let vertices: &[(f32, f32, f32, f32)] = &[
-            (-1.0, -1.0, 0.0, 1.0),
-            ( 1.0, -1.0, 1.0, 1.0),
-            ( 1.0,  1.0, 1.0, 0.0),
-            (-1.0, -1.0, 0.0, 1.0),
-            (-1.0,  1.0, 0.0, 0.0),
-            ( 1.0,  1.0, 1.0, 0.0),
+        let vertices: &[Vertex] = &[
+            Vertex { position: [-1.0, -1.0], uv: [0.0, 1.0] },
+            Vertex { position: [ 1.0, -1.0], uv: [1.0, 1.0] },
+            Vertex { position: [ 1.0,  1.0], uv: [1.0, 0.0] },
+            Vertex { position: [-1.0, -1.0], uv: [0.0, 1.0] },
+            Vertex { position: [-1.0,  1.0], uv: [0.0, 0.0] },
+            Vertex { position: [ 1.0,  1.0], uv: [1.0, 0.0] },
         ];
 
         Self {
@@ -43,7 +51,7 @@ impl Framebuffer {
 }
 
 pub struct FramebufferPipeline {
-    pipeline: core::Pipeline,
+    pipeline: core::Pipeline<Vertex>,
     bindings: core::BindingGroup,
     buf: core::UniformBuffer,
     width: u32,
@@ -53,10 +61,11 @@ pub struct FramebufferPipeline {
 impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
     type PrepareContext = core::Rgba;
     type Uniforms = core::Rgba;
+    type Vertex = Vertex;
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            vertex_layout: &[<Self::Vertex as core::Vertex>::LAYOUT],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -76,10 +85,16 @@ impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/framebuffer.vert.spv"),
             fragment_shader: include_bytes!("data/framebuffer.frag.spv"),
+            index_format: core::IndexFormat::Uint16,
         }
     }
 
-    fn setup(pipeline: core::Pipeline, dev: &core::Device, width: u32, height: u32) -> Self {
+    fn setup(
+        pipeline: core::Pipeline<Vertex>,
+        dev: &core::Device,
+        width: u32,
+        height: u32,
+    ) -> Self {
         let buf = dev.create_uniform_buffer(&[core::Rgba::TRANSPARENT]);
         let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
 
@@ -242,14 +257,14 @@ fn main() {
 
             {
                 let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &framebuffer.target);
-                pass.set_pipeline(&offscreen);
-                pass.draw(&buffer, &offscreen_binding);
+                pass.set_pipeline(&offscreen)
+                    .draw(&buffer, &offscreen_binding);
             }
 
             {
                 let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
-                pass.set_pipeline(&onscreen);
-                pass.draw(&framebuffer.vertices, &onscreen_binding);
+                pass.set_pipeline(&onscreen)
+                    .draw(&framebuffer.vertices, &onscreen_binding);
             }
 
             r.submit(frame);
@@ -17,21 +17,29 @@ use std::fs::File;
 use raw_window_handle::HasRawWindowHandle;
 use winit::{event_loop::EventLoop, window::Window};
 
+rgx::vertex! {
+    #[derive(Copy, Clone)]
+    pub struct Vertex {
+        position: [f32; 2],
+        uv: [f32; 2],
+    }
+}
+
 pub struct Framebuffer {
     target: core::Framebuffer,
-    vertices: core::VertexBuffer,
+    vertices: core::VertexBuffer<Vertex>,
 }
 
 impl Framebuffer {
     fn new(w: u32, h: u32, r: &core::Renderer) -> Self {
         #[rustfmt::skip]
-        let vertices: &[(f32, f32, f32, f32)] = &[
-            (-1.0, -1.0, 0.0, 1.0),
-            ( 1.0, -1.0, 1.0, 1.0),
-            ( 1.0,  1.0, 1.0, 0.0),
-            (-1.0, -1.0, 0.0, 1.0),
-            (-1.0,  1.0, 0.0, 0.0),
-            ( 1.0,  1.0, 1.0, 0.0),
+        let vertices: &[Vertex] = &[
+            Vertex { position: [-1.0, -1.0], uv: [0.0, 1.0] },
+            Vertex { position: [ 1.0, -1.0], uv: [1.0, 1.0] },
+            Vertex { position: [ 1.0,  1.0], uv: [1.0, 0.0] },
+            Vertex { position: [-1.0, -1.0], uv: [0.0, 1.0] },
+            Vertex { position: [-1.0,  1.0], uv: [0.0, 0.0] },
+            Vertex { position: [ 1.0,  1.0], uv: [1.0, 0.0] },
         ];
 
         Self {
@@ -42,7 +50,7 @@ impl Framebuffer {
 }
 
 pub struct FramebufferPipeline {
-    pipeline: core::Pipeline,
+    pipeline: core::Pipeline<Vertex>,
     bindings: core::BindingGroup,
     buf: core::UniformBuffer,
     width: u32,
@@ -52,10 +60,11 @@ pub struct FramebufferPipeline {
 impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
     type PrepareContext = core::Rgba;
     type Uniforms = core::Rgba;
+    type Vertex = Vertex;
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::Float2],
+            vertex_layout: &[<Self::Vertex as core::Vertex>::LAYOUT],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -75,10 +84,16 @@ impl<'a> core::AbstractPipeline<'a> for FramebufferPipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/framebuffer.vert.spv"),
             fragment_shader: include_bytes!("data/framebuffer.frag.spv"),
+            index_format: core::IndexFormat::Uint16,
         }
     }
 
-    fn setup(pipeline: core::Pipeline, dev: &core::Device, width: u32, height: u32) -> Self {
+    fn setup(
+        pipeline: core::Pipeline<Vertex>,
+        dev: &core::Device,
+        width: u32,
+        height: u32,
+    ) -> Self {
         let buf = dev.create_uniform_buffer(&[core::Rgba::TRANSPARENT]);
         let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
 
@@ -180,14 +195,13 @@ fn main() {
 
     {
         let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &framebuffer.target);
-        pass.set_pipeline(&offscreen);
-        pass.draw_buffer(&buffer);
+        pass.set_pipeline(&offscreen).draw_buffer(&buffer);
     }
 
     {
         let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
-        pass.set_pipeline(&onscreen);
-        pass.draw(&framebuffer.vertices, &onscreen_binding);
+        pass.set_pipeline(&onscreen)
+            .draw(&framebuffer.vertices, &onscreen_binding);
     }
 
     // Submit frame first, so that we can read it below.
@@ -197,13 +211,10 @@ fn main() {
     // Read the framebuffer into host memory and write it to an image file
     ///////////////////////////////////////////////////////////////////////////
 
-    let w = framebuffer.target.texture.w;
-    let h = framebuffer.target.texture.h;
-
-    r.read(&framebuffer.target, move |data| {
-        let file = File::create("screenshot.png").unwrap();
-        let png = PNGEncoder::new(file);
+    let image = r.capture(&framebuffer.target);
+    let file = File::create("screenshot.png").unwrap();
+    let png = PNGEncoder::new(file);
 
-        png.encode(data, w, h, ColorType::RGBA(8)).unwrap();
-    });
+    png.encode(image.bytes(), image.w, image.h, ColorType::RGBA(8))
+        .unwrap();
 }
@@ -215,8 +215,7 @@ fn main() {
                 {
                     let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
 
-                    pass.set_pipeline(&pip);
-                    pass.draw(&buffer, &binding);
+                    pass.set_pipeline(&pip).draw(&buffer, &binding);
                 }
 
                 r.submit(frame);
@@ -68,8 +68,7 @@ fn main() {
             {
                 let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &output);
 
-                pass.set_pipeline(&pipeline);
-                pass.draw_buffer(&buffer);
+                pass.set_pipeline(&pipeline).draw_buffer(&buffer);
             }
             renderer.submit(frame);
         }
@@ -0,0 +1,11 @@
+#version 450
+
+layout(location = 0) out vec2 f_uv;
+
+// Full-screen triangle, generated from the vertex index.
+void main() {
+	vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+
+	f_uv = uv;
+	gl_Position = vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
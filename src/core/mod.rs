@@ -2,14 +2,21 @@
 #![allow(clippy::cast_lossless)]
 
 use std::fmt;
-use std::ops::Range;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Range};
+use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 
 use raw_window_handle::RawWindowHandle;
 
 use crate::math;
 use crate::math::{Point2, Vector2};
 
+mod reflect;
+
 ///////////////////////////////////////////////////////////////////////////
 // Rgba8
 ///////////////////////////////////////////////////////////////////////////
@@ -551,8 +558,11 @@ where
 /// Draw
 ///////////////////////////////////////////////////////////////////////////////
 
+/// Something which can be drawn with pipelines of a given vertex type.
 pub trait Draw {
-    fn draw(&self, binding: &BindingGroup, pass: &mut Pass);
+    type Vertex: Vertex;
+
+    fn draw(&self, binding: &BindingGroup, pass: &mut PipelinePass<Self::Vertex>);
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -619,9 +629,14 @@ impl From<Rgba8> for Rgba {
 
 pub struct Shader {
     module: wgpu::ShaderModule,
+    stage: ShaderStage,
+    /// The shader's inputs and bindings, used to validate pipelines. This is
+    /// `None` if the SPIR-V couldn't be reflected, in which case a warning is
+    /// logged and the shader isn't validated.
+    interface: Option<reflect::Module>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ShaderStage {
     Vertex,
     Fragment,
@@ -629,7 +644,7 @@ pub enum ShaderStage {
 }
 
 impl ShaderStage {
-    fn to_wgpu(&self) -> wgpu::ShaderStage {
+    fn to_wgpu(self) -> wgpu::ShaderStage {
         match self {
             ShaderStage::Vertex => wgpu::ShaderStage::VERTEX,
             ShaderStage::Fragment => wgpu::ShaderStage::FRAGMENT,
@@ -639,17 +654,151 @@ impl ShaderStage {
 }
 
 ///////////////////////////////////////////////////////////////////////////////
-/// Canvas
+/// Shader compilation
 ///////////////////////////////////////////////////////////////////////////////
 
-pub trait Canvas {
-    type Color;
+/// A shading language which can be compiled to SPIR-V at runtime. Shaders
+/// must follow the same conventions as precompiled ones, and have their entry
+/// point named `main`.
+#[cfg(feature = "shader-compiler")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShaderLanguage {
+    /// GLSL 4.50, as used by the shaders of this crate.
+    Glsl,
+    /// WGSL. A source may contain entry points for several stages.
+    Wgsl,
+}
+
+/// An error encountered while compiling a shader.
+#[cfg(feature = "shader-compiler")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderError {
+    /// The 1-based line and column of the error in the source, if known.
+    pub location: Option<(u32, u32)>,
+    pub message: String,
+}
+
+#[cfg(feature = "shader-compiler")]
+impl ShaderError {
+    fn new(err: &dyn std::error::Error, location: Option<naga::SourceLocation>) -> Self {
+        let mut message = err.to_string();
+        let mut source = err.source();
+
+        while let Some(err) = source {
+            message = format!("{}: {}", message, err);
+            source = err.source();
+        }
+        Self {
+            location: location.map(|l| (l.line_number, l.line_position)),
+            message,
+        }
+    }
+}
+
+#[cfg(feature = "shader-compiler")]
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(feature = "shader-compiler")]
+impl std::error::Error for ShaderError {}
+
+/// Compile shader source code to SPIR-V, as expected by
+/// `Device::create_shader`.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::{compile_shader, ShaderLanguage, ShaderStage};
+///
+/// let src = "#version 450
+/// layout(location = 0) out vec4 color;
+///
+/// void main() {
+///     color = vec4(1.0);
+/// }";
+/// let spirv = compile_shader(src, ShaderLanguage::Glsl, ShaderStage::Fragment).unwrap();
+/// assert_eq!(&spirv[..4], &[0x03, 0x02, 0x23, 0x07]);
+///
+/// let src = "#version 450
+/// void main() {
+///     undefined();
+/// }";
+/// let err = compile_shader(src, ShaderLanguage::Glsl, ShaderStage::Fragment).unwrap_err();
+/// assert_eq!(err.location.map(|(line, _)| line), Some(3));
+/// ```
+#[cfg(feature = "shader-compiler")]
+pub fn compile_shader(
+    source: &str,
+    lang: ShaderLanguage,
+    stage: ShaderStage,
+) -> Result<Vec<u8>, ShaderError> {
+    use naga::back::spv;
+    use naga::front::{glsl, wgsl};
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+    let shader_stage = match stage {
+        ShaderStage::Vertex => naga::ShaderStage::Vertex,
+        ShaderStage::Fragment => naga::ShaderStage::Fragment,
+        ShaderStage::Compute => naga::ShaderStage::Compute,
+    };
+    let module = match lang {
+        ShaderLanguage::Glsl => glsl::Frontend::default()
+            .parse(&glsl::Options::from(shader_stage), source)
+            .map_err(|errors| {
+                // Only the first error is reported, since the following ones
+                // are often caused by it.
+                let err = &errors[0];
+                let location = if err.meta.is_defined() {
+                    Some(err.meta.location(source))
+                } else {
+                    None
+                };
+                ShaderError::new(&err.kind, location)
+            })?,
+        ShaderLanguage::Wgsl => {
+            wgsl::parse_str(source).map_err(|err| ShaderError::new(&err, err.location(source)))?
+        }
+    };
+    let info = Validator::new(ValidationFlags::all(), Capabilities::empty())
+        .validate(&module)
+        .map_err(|err| ShaderError::new(err.as_inner(), err.location(source)))?;
+
+    let mut options = spv::Options::default();
+    // Shaders are written with the Y axis pointing up, like `wgpu` expects.
+    options
+        .flags
+        .remove(spv::WriterFlags::ADJUST_COORDINATE_SPACE);
+
+    let pipeline = spv::PipelineOptions {
+        shader_stage,
+        entry_point: String::from("main"),
+    };
+    let words = spv::write_vec(&module, &info, &options, Some(&pipeline))
+        .map_err(|err| ShaderError::new(&err, None))?;
+
+    Ok(words
+        .iter()
+        .flat_map(|w| w.to_le_bytes().to_vec())
+        .collect())
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Canvas
+///////////////////////////////////////////////////////////////////////////////
 
-    fn clear(&self, color: Self::Color, device: &mut Device, encoder: &mut wgpu::CommandEncoder);
-    fn fill(&self, buf: &[Self::Color], device: &mut Device, encoder: &mut wgpu::CommandEncoder);
+/// A surface which can be written to with texels of type `T`.
+pub trait Canvas<T> {
+    fn clear(&self, color: T, device: &mut Device, encoder: &mut wgpu::CommandEncoder);
+    fn fill(&self, buf: &[T], device: &mut Device, encoder: &mut wgpu::CommandEncoder);
     fn transfer(
         &self,
-        buf: &[Self::Color],
+        buf: &[T],
         w: u32,
         h: u32,
         r: Rect<i32>,
@@ -720,6 +869,38 @@ impl Bind for UniformBuffer {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// Storage
+///////////////////////////////////////////////////////////////////////////////
+
+/// A buffer which shaders can read and write, bound in a 'BindingGroup' with
+/// `BindingType::StorageBuffer`. It can also be used as the vertex buffer of
+/// a render pass, eg. to draw particles simulated in a compute pass.
+pub struct StorageBuffer {
+    wgpu: wgpu::Buffer,
+    size: usize,
+    count: usize,
+}
+
+impl StorageBuffer {
+    /// The number of elements in the buffer.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Bind for StorageBuffer {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        wgpu::Binding {
+            binding: index,
+            resource: wgpu::BindingResource::Buffer {
+                buffer: &self.wgpu,
+                range: 0..((self.size * self.count) as wgpu::BufferAddress),
+            },
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Framebuffer
 ///////////////////////////////////////////////////////////////////////////////
@@ -752,20 +933,18 @@ impl Bind for Framebuffer {
     }
 }
 
-impl Canvas for Framebuffer {
-    type Color = Bgra8;
-
-    fn clear(&self, color: Bgra8, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+impl<T: Pixel> Canvas<T> for Framebuffer {
+    fn clear(&self, color: T, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::clear(&self.texture, color, device, encoder);
     }
 
-    fn fill(&self, buf: &[Bgra8], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+    fn fill(&self, buf: &[T], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::fill(&self.texture, buf, device, encoder);
     }
 
     fn transfer(
         &self,
-        buf: &[Bgra8],
+        buf: &[T],
         w: u32,
         h: u32,
         rect: Rect<i32>,
@@ -790,17 +969,242 @@ impl TextureView for Framebuffer {
 /// Texturing
 ///////////////////////////////////////////////////////////////////////////////
 
+/// The format of a texture's texels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum TextureFormat {
+    /// 8-bit RGBA, stored linearly.
+    #[default]
+    Rgba8,
+    /// 8-bit RGBA, stored in sRGB space and converted on sampling.
+    Rgba8Srgb,
+    /// 8-bit BGRA, stored linearly. This is the default swap chain format.
+    Bgra8,
+    /// 8-bit BGRA, stored in sRGB space and converted on sampling. Swap
+    /// chains of this format encode their output to sRGB.
+    Bgra8Srgb,
+    /// Single 8-bit channel, eg. for masks and glyphs.
+    R8,
+    /// 16-bit floating point RGBA, eg. for HDR rendering.
+    Rgba16Float,
+}
+
+impl TextureFormat {
+    /// Whether the format is stored in sRGB space.
+    pub fn is_srgb(self) -> bool {
+        self == Self::Rgba8Srgb || self == Self::Bgra8Srgb
+    }
+
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            Self::Rgba8 => wgpu::TextureFormat::Rgba8Unorm,
+            Self::Rgba8Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            Self::Bgra8 => wgpu::TextureFormat::Bgra8Unorm,
+            Self::Bgra8Srgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+            Self::R8 => wgpu::TextureFormat::R8Unorm,
+            Self::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+/// A texel type which can be copied to and from textures of matching
+/// formats.
+///
+/// # Safety
+///
+/// Implementors must be plain data with the exact size and layout of a texel
+/// in all formats they match, and be valid for any bit pattern.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::{Bgra8, Pixel, Rgba8, TextureFormat};
+///
+/// assert!(Rgba8::matches(TextureFormat::Rgba8Srgb));
+/// assert!(!Bgra8::matches(TextureFormat::Rgba8));
+/// assert!(u8::matches(TextureFormat::R8));
+/// ```
+pub unsafe trait Pixel: Copy + Send + 'static {
+    /// Whether this texel type matches the given format.
+    fn matches(format: TextureFormat) -> bool;
+}
+
+unsafe impl Pixel for Rgba8 {
+    fn matches(format: TextureFormat) -> bool {
+        format == TextureFormat::Rgba8 || format == TextureFormat::Rgba8Srgb
+    }
+}
+
+unsafe impl Pixel for Bgra8 {
+    fn matches(format: TextureFormat) -> bool {
+        format == TextureFormat::Bgra8 || format == TextureFormat::Bgra8Srgb
+    }
+}
+
+unsafe impl Pixel for u8 {
+    fn matches(format: TextureFormat) -> bool {
+        format == TextureFormat::R8
+    }
+}
+
+/// Half-float RGBA, as raw IEEE 754 binary16 bits.
+unsafe impl Pixel for [u16; 4] {
+    fn matches(format: TextureFormat) -> bool {
+        format == TextureFormat::Rgba16Float
+    }
+}
+
+/// The ways in which a texture can be used.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::TextureUsage;
+///
+/// let usage = TextureUsage::SAMPLED | TextureUsage::COPY_SRC;
+///
+/// assert!(usage.contains(TextureUsage::COPY_SRC));
+/// assert!(!usage.contains(TextureUsage::COPY_SRC | TextureUsage::COPY_DST));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureUsage(u32);
+
+impl TextureUsage {
+    /// The texture can be copied from, eg. to read it back.
+    pub const COPY_SRC: Self = Self(1);
+    /// The texture can be copied to, eg. to fill it.
+    pub const COPY_DST: Self = Self(1 << 1);
+    /// The texture can be bound and sampled from in a shader.
+    pub const SAMPLED: Self = Self(1 << 2);
+    /// The texture can be bound as storage in a shader.
+    pub const STORAGE: Self = Self(1 << 3);
+    /// The texture can be rendered to.
+    pub const OUTPUT_ATTACHMENT: Self = Self(1 << 4);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn to_wgpu(self) -> wgpu::TextureUsage {
+        let flags = [
+            (Self::COPY_SRC, wgpu::TextureUsage::COPY_SRC),
+            (Self::COPY_DST, wgpu::TextureUsage::COPY_DST),
+            (Self::SAMPLED, wgpu::TextureUsage::SAMPLED),
+            (Self::STORAGE, wgpu::TextureUsage::STORAGE),
+            (
+                Self::OUTPUT_ATTACHMENT,
+                wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            ),
+        ];
+        flags
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .fold(wgpu::TextureUsage::empty(), |acc, (_, usage)| acc | *usage)
+    }
+}
+
+impl std::ops::BitOr for TextureUsage {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Texture creation options.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextureOptions {
+    pub format: TextureFormat,
+    pub usage: TextureUsage,
+    /// Number of mip levels, including the base level.
+    pub mip_levels: u32,
+    /// Number of array layers.
+    pub layers: u32,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            format: TextureFormat::default(),
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+            mip_levels: 1,
+            layers: 1,
+        }
+    }
+}
+
+impl TextureOptions {
+    /// Options for a texture with a full mip chain, which can be generated
+    /// with `Renderer::generate_mipmaps`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::TextureOptions;
+    ///
+    /// assert_eq!(TextureOptions::mipmapped(256, 64).mip_levels, 9);
+    /// assert_eq!(TextureOptions::mipmapped(1, 1).mip_levels, 1);
+    /// ```
+    pub fn mipmapped(w: u32, h: u32) -> Self {
+        let opts = Self::default();
+
+        Self {
+            usage: opts.usage | TextureUsage::OUTPUT_ATTACHMENT,
+            mip_levels: 32 - u32::max(w, h).max(1).leading_zeros(),
+            ..opts
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub struct Texture {
     wgpu: wgpu::Texture,
     view: wgpu::TextureView,
     extent: wgpu::Extent3d,
+    format: TextureFormat,
+    usage: TextureUsage,
+    mip_levels: u32,
+    layers: u32,
 
     pub w: u32,
     pub h: u32,
 }
 
 impl Texture {
+    pub fn usage(&self) -> TextureUsage {
+        self.usage
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// The size of the given mip level.
+    pub fn level_size(&self, level: u32) -> (u32, u32) {
+        (u32::max(self.w >> level, 1), u32::max(self.h >> level, 1))
+    }
+
+    fn level_view(&self, level: u32, layer: u32) -> MipLevel {
+        MipLevel {
+            view: self.wgpu.create_view(&wgpu::TextureViewDescriptor {
+                format: self.format.to_wgpu(),
+                dimension: wgpu::TextureViewDimension::D2,
+                aspect: wgpu::TextureAspect::All,
+                base_mip_level: level,
+                level_count: 1,
+                base_array_layer: layer,
+                array_layer_count: 1,
+            }),
+        }
+    }
+
     pub fn rect(&self) -> Rect<f32> {
         Rect {
             x1: 0.0,
@@ -810,32 +1214,33 @@ impl Texture {
         }
     }
 
-    fn clear<T>(
+    fn check_format<T: Pixel>(&self) {
+        assert!(
+            T::matches(self.format),
+            "texel type `{}` doesn't match texture format {:?}",
+            std::any::type_name::<T>(),
+            self.format
+        );
+    }
+
+    fn clear<T: Pixel>(
         texture: &Texture,
         color: T,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
-    ) where
-        T: Into<Rgba8> + Clone,
-    {
-        let mut texels: Vec<T> = Vec::with_capacity(texture.w as usize * texture.h as usize);
-        texels.resize(texture.w as usize * texture.h as usize, color);
-
-        let (head, body, tail) = unsafe { texels.align_to::<Rgba8>() };
-        assert!(head.is_empty());
-        assert!(tail.is_empty());
+    ) {
+        let texels = vec![color; texture.w as usize * texture.h as usize];
 
-        Self::fill(texture, body, device, encoder);
+        Self::fill(texture, &texels, device, encoder);
     }
 
-    fn fill<T: 'static>(
+    fn fill<T: Pixel>(
         texture: &Texture,
         texels: &[T],
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
-    ) where
-        T: Into<Rgba8> + Clone + Copy,
-    {
+    ) {
+        texture.check_format::<T>();
         assert_eq!(
             texels.len() as u32,
             texture.w * texture.h,
@@ -847,7 +1252,7 @@ impl Texture {
             .create_buffer_mapped(texels.len(), wgpu::BufferUsage::COPY_SRC)
             .fill_from_slice(&texels);
 
-        Self::copy(
+        Self::copy::<T>(
             &texture.wgpu,
             texture.w,
             texture.h,
@@ -859,7 +1264,7 @@ impl Texture {
         );
     }
 
-    fn transfer<T: 'static>(
+    fn transfer<T: Pixel>(
         texture: &Texture,
         texels: &[T],
         width: u32,
@@ -867,9 +1272,9 @@ impl Texture {
         rect: Rect<i32>,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
-    ) where
-        T: Into<Rgba8> + Clone + Copy,
-    {
+    ) {
+        texture.check_format::<T>();
+
         // Wgpu's coordinate system has a downwards pointing Y axis.
         let rect = rect.normalized().flip_y();
 
@@ -901,7 +1306,7 @@ impl Texture {
             height: tx_h,
             depth: 1,
         };
-        Self::copy(
+        Self::copy::<T>(
             &texture.wgpu,
             width,
             height,
@@ -954,7 +1359,7 @@ impl Texture {
         );
     }
 
-    fn copy(
+    fn copy<T>(
         texture: &wgpu::Texture,
         w: u32,
         h: u32,
@@ -968,7 +1373,7 @@ impl Texture {
             wgpu::BufferCopyView {
                 buffer,
                 offset: 0,
-                row_pitch: 4 * w,
+                row_pitch: std::mem::size_of::<T>() as u32 * w,
                 image_height: h,
             },
             wgpu::TextureCopyView {
@@ -991,20 +1396,18 @@ impl Bind for Texture {
     }
 }
 
-impl Canvas for Texture {
-    type Color = Rgba8;
-
-    fn fill(&self, buf: &[Rgba8], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+impl<T: Pixel> Canvas<T> for Texture {
+    fn fill(&self, buf: &[T], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::fill(&self, buf, device, encoder);
     }
 
-    fn clear(&self, color: Rgba8, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+    fn clear(&self, color: T, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::clear(&self, color, device, encoder);
     }
 
     fn transfer(
         &self,
-        buf: &[Rgba8],
+        buf: &[T],
         w: u32,
         h: u32,
         rect: Rect<i32>,
@@ -1025,10 +1428,106 @@ impl From<Framebuffer> for Texture {
     }
 }
 
+/// A view of a single mip level and array layer of a texture.
+struct MipLevel {
+    view: wgpu::TextureView,
+}
+
+impl Bind for MipLevel {
+    fn binding(&self, index: u32) -> wgpu::Binding<'_> {
+        wgpu::Binding {
+            binding: index,
+            resource: wgpu::BindingResource::TextureView(&self.view),
+        }
+    }
+}
+
 pub struct Sampler {
     wgpu: wgpu::Sampler,
 }
 
+/// Sampler configuration. The defaults are nearest filtering, repeating
+/// coordinates on all axes, all mip levels, and no comparison.
+///
+/// Anisotropic filtering isn't configured per sampler: it applies to all
+/// samplers of a device created with it enabled, see `Extensions`.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::{AddressMode, CompareFunction, Filter, SamplerDescriptor};
+///
+/// let desc = SamplerDescriptor::default()
+///     .filter(Filter::Linear, Filter::Nearest)
+///     .address_mode(AddressMode::ClampToEdge)
+///     .compare(CompareFunction::LessEqual);
+///
+/// assert_eq!(desc.min_filter, Filter::Linear);
+/// assert_eq!(desc.address_mode_v, AddressMode::ClampToEdge);
+/// assert_eq!(desc.compare, Some(CompareFunction::LessEqual));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SamplerDescriptor {
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    /// Filter used between mip levels.
+    pub mipmap_filter: Filter,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    pub address_mode_w: AddressMode,
+    /// Range of mip levels that can be sampled from.
+    pub lod: Range<f32>,
+    /// Comparison function, for comparison samplers such as used for
+    /// shadow maps.
+    pub compare: Option<CompareFunction>,
+}
+
+impl SamplerDescriptor {
+    pub fn filter(mut self, min: Filter, mag: Filter) -> Self {
+        self.min_filter = min;
+        self.mag_filter = mag;
+        self
+    }
+
+    pub fn mipmap_filter(mut self, filter: Filter) -> Self {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Set the address mode of all axes.
+    pub fn address_mode(mut self, mode: AddressMode) -> Self {
+        self.address_mode_u = mode;
+        self.address_mode_v = mode;
+        self.address_mode_w = mode;
+        self
+    }
+
+    pub fn lod(mut self, lod: Range<f32>) -> Self {
+        self.lod = lod;
+        self
+    }
+
+    pub fn compare(mut self, f: CompareFunction) -> Self {
+        self.compare = Some(f);
+        self
+    }
+}
+
+impl Default for SamplerDescriptor {
+    fn default() -> Self {
+        Self {
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+            mipmap_filter: Filter::Nearest,
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            address_mode_w: AddressMode::Repeat,
+            lod: -100.0..100.0,
+            compare: None,
+        }
+    }
+}
+
 impl Bind for Sampler {
     fn binding(&self, index: u32) -> wgpu::Binding {
         wgpu::Binding {
@@ -1038,14 +1537,14 @@ impl Bind for Sampler {
     }
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Filter {
     Nearest,
     Linear,
 }
 
 impl Filter {
-    fn to_wgpu(&self) -> wgpu::FilterMode {
+    fn to_wgpu(self) -> wgpu::FilterMode {
         match self {
             Filter::Nearest => wgpu::FilterMode::Nearest,
             Filter::Linear => wgpu::FilterMode::Linear,
@@ -1053,108 +1552,690 @@ impl Filter {
     }
 }
 
+/// How texture coordinates outside of the `0..1` range are handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddressMode {
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl AddressMode {
+    fn to_wgpu(self) -> wgpu::AddressMode {
+        match self {
+            Self::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            Self::Repeat => wgpu::AddressMode::Repeat,
+            Self::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+/// A comparison function, used by comparison samplers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunction {
+    fn to_wgpu(self) -> wgpu::CompareFunction {
+        match self {
+            Self::Never => wgpu::CompareFunction::Never,
+            Self::Less => wgpu::CompareFunction::Less,
+            Self::Equal => wgpu::CompareFunction::Equal,
+            Self::LessEqual => wgpu::CompareFunction::LessEqual,
+            Self::Greater => wgpu::CompareFunction::Greater,
+            Self::NotEqual => wgpu::CompareFunction::NotEqual,
+            Self::GreaterEqual => wgpu::CompareFunction::GreaterEqual,
+            Self::Always => wgpu::CompareFunction::Always,
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Vertex/Index Buffers
 ///////////////////////////////////////////////////////////////////////////////
 
-pub struct VertexBuffer {
+/// A buffer of vertices of type `V`.
+pub struct VertexBuffer<V> {
     pub size: u32,
     wgpu: wgpu::Buffer,
+    vertex: PhantomData<V>,
 }
 
-impl Draw for VertexBuffer {
-    fn draw(&self, binding: &BindingGroup, pass: &mut Pass) {
-        // TODO: If we attempt to draw more vertices than exist in the buffer, because
-        // 'size' was guessed wrong, we get a wgpu error. We should somehow try to
-        // get the pipeline layout to know here if the buffer we're trying to draw
-        // is the right size. Another option is to create buffers from the pipeline,
-        // so that we can check at creation time whether the data passed in matches
-        // the format.
+impl<V: Vertex> Draw for VertexBuffer<V> {
+    type Vertex = V;
+
+    fn draw(&self, binding: &BindingGroup, pass: &mut PipelinePass<V>) {
         pass.set_binding(binding, &[]);
         pass.draw_buffer(&self);
     }
 }
 
-pub struct IndexBuffer {
-    wgpu: wgpu::Buffer,
+/// A vertex type. Its attributes are laid out in the order of its formats,
+/// without padding, and are read by shaders at consecutive locations
+/// starting from zero. Usually implemented with the `vertex!` macro.
+pub trait Vertex: Copy + 'static {
+    const FORMATS: &'static [VertexFormat];
+    /// The layout of a vertex buffer slot holding this type, advancing once
+    /// per vertex.
+    const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        formats: Self::FORMATS,
+        step_mode: StepMode::Vertex,
+    };
 }
 
-#[derive(Clone, Copy)]
-pub enum VertexFormat {
-    Float,
-    Float2,
-    Float3,
-    Float4,
-    UByte4,
+/// The vertex type of pipelines without vertex buffers, eg. ones generating
+/// their vertices in the vertex shader.
+impl Vertex for () {
+    const FORMATS: &'static [VertexFormat] = &[];
 }
 
-impl VertexFormat {
-    // TODO: Use `const fn`
-    fn bytesize(self) -> usize {
-        match self {
-            VertexFormat::Float => 4,
-            VertexFormat::Float2 => 8,
-            VertexFormat::Float3 => 12,
-            VertexFormat::Float4 => 16,
-            VertexFormat::UByte4 => 4,
-        }
-    }
-    // TODO: Use `const fn`
-    fn to_wgpu(self) -> wgpu::VertexFormat {
-        match self {
-            VertexFormat::Float => wgpu::VertexFormat::Float,
-            VertexFormat::Float2 => wgpu::VertexFormat::Float2,
-            VertexFormat::Float3 => wgpu::VertexFormat::Float3,
-            VertexFormat::Float4 => wgpu::VertexFormat::Float4,
-            VertexFormat::UByte4 => wgpu::VertexFormat::Uchar4Norm,
-        }
-    }
+/// A type which can be used as a vertex attribute by the `vertex!` macro.
+///
+/// # Examples
+///
+/// Two-byte attributes can be mixed with four-byte ones, as long as the
+/// struct needs no padding, eg. by pairing them up:
+///
+/// ```
+/// use rgx::core::{Half, Norm, Vertex, VertexFormat};
+///
+/// rgx::vertex! {
+///     #[derive(Copy, Clone)]
+///     struct Packed {
+///         position: [f32; 2],
+///         uv: Norm<[u16; 2]>,
+///         normal: Norm<[i8; 2]>,
+///         layer: [u8; 2],
+///         scale: Half<[u16; 2]>,
+///     }
+/// }
+///
+/// assert_eq!(std::mem::size_of::<Packed>(), 20);
+/// assert_eq!(
+///     Packed::FORMATS,
+///     &[
+///         VertexFormat::Float2,
+///         VertexFormat::UShort2Norm,
+///         VertexFormat::Char2Norm,
+///         VertexFormat::UChar2,
+///         VertexFormat::Half2,
+///     ]
+/// );
+/// ```
+pub trait VertexAttribute: Copy + 'static {
+    const FORMAT: VertexFormat;
 }
 
-/// Describes a 'VertexBuffer' layout.
-#[derive(Default)]
-pub struct VertexLayout {
-    wgpu_attrs: Vec<wgpu::VertexAttributeDescriptor>,
-    size: usize,
+impl VertexAttribute for f32 {
+    const FORMAT: VertexFormat = VertexFormat::Float;
 }
 
-impl VertexLayout {
-    pub fn from(formats: &[VertexFormat]) -> Self {
-        let mut vl = Self::default();
-        for vf in formats {
-            vl.wgpu_attrs.push(wgpu::VertexAttributeDescriptor {
-                shader_location: vl.wgpu_attrs.len() as u32,
-                offset: vl.size as wgpu::BufferAddress,
-                format: vf.to_wgpu(),
-            });
-            vl.size += vf.bytesize();
-        }
-        vl
-    }
+impl VertexAttribute for [f32; 2] {
+    const FORMAT: VertexFormat = VertexFormat::Float2;
+}
 
-    fn to_wgpu(&self) -> wgpu::VertexBufferDescriptor {
-        wgpu::VertexBufferDescriptor {
-            stride: self.size as wgpu::BufferAddress,
-            step_mode: wgpu::InputStepMode::Vertex,
-            attributes: self.wgpu_attrs.as_slice(),
-        }
-    }
+impl VertexAttribute for [f32; 3] {
+    const FORMAT: VertexFormat = VertexFormat::Float3;
 }
 
-///////////////////////////////////////////////////////////////////////////////
-/// Pipeline Bindings
-///////////////////////////////////////////////////////////////////////////////
+impl VertexAttribute for [f32; 4] {
+    const FORMAT: VertexFormat = VertexFormat::Float4;
+}
 
-/// A binding type.
-pub enum BindingType {
-    UniformBuffer,
-    UniformBufferDynamic,
-    Sampler,
-    SampledTexture,
+impl VertexAttribute for math::Vector2<f32> {
+    const FORMAT: VertexFormat = VertexFormat::Float2;
+}
+
+impl VertexAttribute for math::Vector3<f32> {
+    const FORMAT: VertexFormat = VertexFormat::Float3;
+}
+
+impl VertexAttribute for math::Vector4<f32> {
+    const FORMAT: VertexFormat = VertexFormat::Float4;
+}
+
+impl VertexAttribute for Point2<f32> {
+    const FORMAT: VertexFormat = VertexFormat::Float2;
+}
+
+impl VertexAttribute for Rgba {
+    const FORMAT: VertexFormat = VertexFormat::Float4;
+}
+
+/// Normalized, so that shaders read it as a `vec4` in the `0..1` range.
+impl VertexAttribute for Rgba8 {
+    const FORMAT: VertexFormat = VertexFormat::UChar4Norm;
+}
+
+/// A vertex attribute of integers which shaders read as normalized floats,
+/// eg. `Norm<[u8; 4]>` is read as a `vec4` in the `0..1` range.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Norm<T>(pub T);
+
+/// A vertex attribute of 16-bit floats, given as raw IEEE 754 binary16 bits.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Half<T>(pub T);
+
+impl VertexAttribute for u32 {
+    const FORMAT: VertexFormat = VertexFormat::UInt;
+}
+
+impl VertexAttribute for [u32; 2] {
+    const FORMAT: VertexFormat = VertexFormat::UInt2;
+}
+
+impl VertexAttribute for [u32; 3] {
+    const FORMAT: VertexFormat = VertexFormat::UInt3;
+}
+
+impl VertexAttribute for [u32; 4] {
+    const FORMAT: VertexFormat = VertexFormat::UInt4;
+}
+
+impl VertexAttribute for i32 {
+    const FORMAT: VertexFormat = VertexFormat::Int;
+}
+
+impl VertexAttribute for [i32; 2] {
+    const FORMAT: VertexFormat = VertexFormat::Int2;
+}
+
+impl VertexAttribute for [i32; 3] {
+    const FORMAT: VertexFormat = VertexFormat::Int3;
+}
+
+impl VertexAttribute for [i32; 4] {
+    const FORMAT: VertexFormat = VertexFormat::Int4;
+}
+
+impl VertexAttribute for [u8; 2] {
+    const FORMAT: VertexFormat = VertexFormat::UChar2;
+}
+
+impl VertexAttribute for [u8; 4] {
+    const FORMAT: VertexFormat = VertexFormat::UChar4;
+}
+
+impl VertexAttribute for [i8; 2] {
+    const FORMAT: VertexFormat = VertexFormat::Char2;
+}
+
+impl VertexAttribute for [i8; 4] {
+    const FORMAT: VertexFormat = VertexFormat::Char4;
+}
+
+impl VertexAttribute for [u16; 2] {
+    const FORMAT: VertexFormat = VertexFormat::UShort2;
+}
+
+impl VertexAttribute for [u16; 4] {
+    const FORMAT: VertexFormat = VertexFormat::UShort4;
+}
+
+impl VertexAttribute for [i16; 2] {
+    const FORMAT: VertexFormat = VertexFormat::Short2;
+}
+
+impl VertexAttribute for [i16; 4] {
+    const FORMAT: VertexFormat = VertexFormat::Short4;
+}
+
+impl VertexAttribute for Norm<[u8; 2]> {
+    const FORMAT: VertexFormat = VertexFormat::UChar2Norm;
+}
+
+impl VertexAttribute for Norm<[u8; 4]> {
+    const FORMAT: VertexFormat = VertexFormat::UChar4Norm;
+}
+
+impl VertexAttribute for Norm<[i8; 2]> {
+    const FORMAT: VertexFormat = VertexFormat::Char2Norm;
+}
+
+impl VertexAttribute for Norm<[i8; 4]> {
+    const FORMAT: VertexFormat = VertexFormat::Char4Norm;
+}
+
+impl VertexAttribute for Norm<[u16; 2]> {
+    const FORMAT: VertexFormat = VertexFormat::UShort2Norm;
+}
+
+impl VertexAttribute for Norm<[u16; 4]> {
+    const FORMAT: VertexFormat = VertexFormat::UShort4Norm;
+}
+
+impl VertexAttribute for Norm<[i16; 2]> {
+    const FORMAT: VertexFormat = VertexFormat::Short2Norm;
+}
+
+impl VertexAttribute for Norm<[i16; 4]> {
+    const FORMAT: VertexFormat = VertexFormat::Short4Norm;
+}
+
+impl VertexAttribute for Half<[u16; 2]> {
+    const FORMAT: VertexFormat = VertexFormat::Half2;
+}
+
+impl VertexAttribute for Half<[u16; 4]> {
+    const FORMAT: VertexFormat = VertexFormat::Half4;
+}
+
+/// Define a `#[repr(C)]` vertex struct, and implement `Vertex` for it, with
+/// the format of each field given by its `VertexAttribute` implementation.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::{Rgba8, Vertex, VertexFormat};
+/// use rgx::math::Vector2;
+///
+/// rgx::vertex! {
+///     #[derive(Copy, Clone, Debug)]
+///     pub struct Particle {
+///         pub position: Vector2<f32>,
+///         pub color: Rgba8,
+///         size: f32,
+///     }
+/// }
+///
+/// assert_eq!(
+///     Particle::FORMATS,
+///     &[VertexFormat::Float2, VertexFormat::UChar4Norm, VertexFormat::Float]
+/// );
+/// ```
+///
+/// Attribute offsets follow from the formats, so the struct can't have
+/// padding between or after its fields. Structs which would, eg. a two-byte
+/// field followed by a four-byte one, don't compile. Order the fields by
+/// alignment instead:
+///
+/// ```compile_fail
+/// use rgx::core::Norm;
+///
+/// rgx::vertex! {
+///     #[derive(Copy, Clone)]
+///     struct Padded {
+///         uv: Norm<[u8; 2]>,
+///         depth: f32,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! vertex {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::core::Vertex for $name {
+            const FORMATS: &'static [$crate::core::VertexFormat] = &[
+                $(<$ty as $crate::core::VertexAttribute>::FORMAT),*
+            ];
+        }
+
+        // Fails with an array size mismatch if the struct has padding.
+        const _: [(); 0] =
+            [(); ::std::mem::size_of::<$name>() - (0 $(+ ::std::mem::size_of::<$ty>())*)];
+    };
+}
+
+/// A buffer of vertex indices.
+pub struct IndexBuffer {
+    pub size: u32,
+    wgpu: wgpu::Buffer,
+    format: IndexFormat,
+}
+
+impl IndexBuffer {
+    pub fn format(&self) -> IndexFormat {
+        self.format
+    }
+}
+
+/// The format of vertex indices, set per pipeline by its description. Index
+/// buffers must have the format of the pipeline they're drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IndexFormat {
+    #[default]
+    Uint16,
+    Uint32,
+}
+
+impl IndexFormat {
+    fn to_wgpu(self) -> wgpu::IndexFormat {
+        match self {
+            Self::Uint16 => wgpu::IndexFormat::Uint16,
+            Self::Uint32 => wgpu::IndexFormat::Uint32,
+        }
+    }
+}
+
+/// An integer type which can be used as a vertex index.
+pub trait IndexType: Copy + 'static {
+    const FORMAT: IndexFormat;
+}
+
+impl IndexType for u16 {
+    const FORMAT: IndexFormat = IndexFormat::Uint16;
+}
+
+impl IndexType for u32 {
+    const FORMAT: IndexFormat = IndexFormat::Uint32;
+}
+
+/// A vertex buffer, drawn with an index buffer. The index format must match
+/// the one of the pipeline it's drawn with.
+pub struct IndexedMesh<V> {
+    pub vertices: VertexBuffer<V>,
+    pub indices: IndexBuffer,
+}
+
+impl<V> IndexedMesh<V> {
+    pub fn new(vertices: VertexBuffer<V>, indices: IndexBuffer) -> Self {
+        Self { vertices, indices }
+    }
+}
+
+impl<V: Vertex> Draw for IndexedMesh<V> {
+    type Vertex = V;
+
+    fn draw(&self, binding: &BindingGroup, pass: &mut PipelinePass<V>) {
+        pass.set_binding(binding, &[]);
+        pass.set_vertex_buffer(0, &self.vertices);
+        pass.set_index_buffer(&self.indices);
+        pass.draw_indexed(0..self.indices.size, 0..1);
+    }
+}
+
+/// The format of a vertex attribute. Formats ending in `Norm` are read by
+/// shaders as floats, in the `0..1` range for unsigned formats, and the
+/// `-1..1` range for signed ones. Other integer formats are read as integers,
+/// eg. `UChar4` as a `uvec4`. `Half` formats are 16-bit floats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VertexFormat {
+    UChar2,
+    UChar4,
+    Char2,
+    Char4,
+    UChar2Norm,
+    UChar4Norm,
+    Char2Norm,
+    Char4Norm,
+    UShort2,
+    UShort4,
+    Short2,
+    Short4,
+    UShort2Norm,
+    UShort4Norm,
+    Short2Norm,
+    Short4Norm,
+    Half2,
+    Half4,
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    UInt,
+    UInt2,
+    UInt3,
+    UInt4,
+    Int,
+    Int2,
+    Int3,
+    Int4,
+    /// Four normalized unsigned bytes, eg. an 8-bit color.
+    #[deprecated(note = "use UChar4Norm")]
+    UByte4,
+}
+
+#[allow(deprecated)]
+impl VertexFormat {
+    // TODO: Use `const fn`
+    fn bytesize(self) -> usize {
+        match self {
+            Self::UChar2 => 2,
+            Self::UChar4 => 4,
+            Self::Char2 => 2,
+            Self::Char4 => 4,
+            Self::UChar2Norm => 2,
+            Self::UChar4Norm | Self::UByte4 => 4,
+            Self::Char2Norm => 2,
+            Self::Char4Norm => 4,
+            Self::UShort2 => 4,
+            Self::UShort4 => 8,
+            Self::Short2 => 4,
+            Self::Short4 => 8,
+            Self::UShort2Norm => 4,
+            Self::UShort4Norm => 8,
+            Self::Short2Norm => 4,
+            Self::Short4Norm => 8,
+            Self::Half2 => 4,
+            Self::Half4 => 8,
+            Self::Float => 4,
+            Self::Float2 => 8,
+            Self::Float3 => 12,
+            Self::Float4 => 16,
+            Self::UInt => 4,
+            Self::UInt2 => 8,
+            Self::UInt3 => 12,
+            Self::UInt4 => 16,
+            Self::Int => 4,
+            Self::Int2 => 8,
+            Self::Int3 => 12,
+            Self::Int4 => 16,
+        }
+    }
+    // TODO: Use `const fn`
+    fn to_wgpu(self) -> wgpu::VertexFormat {
+        match self {
+            Self::UChar2 => wgpu::VertexFormat::Uchar2,
+            Self::UChar4 => wgpu::VertexFormat::Uchar4,
+            Self::Char2 => wgpu::VertexFormat::Char2,
+            Self::Char4 => wgpu::VertexFormat::Char4,
+            Self::UChar2Norm => wgpu::VertexFormat::Uchar2Norm,
+            Self::UChar4Norm | Self::UByte4 => wgpu::VertexFormat::Uchar4Norm,
+            Self::Char2Norm => wgpu::VertexFormat::Char2Norm,
+            Self::Char4Norm => wgpu::VertexFormat::Char4Norm,
+            Self::UShort2 => wgpu::VertexFormat::Ushort2,
+            Self::UShort4 => wgpu::VertexFormat::Ushort4,
+            Self::Short2 => wgpu::VertexFormat::Short2,
+            Self::Short4 => wgpu::VertexFormat::Short4,
+            Self::UShort2Norm => wgpu::VertexFormat::Ushort2Norm,
+            Self::UShort4Norm => wgpu::VertexFormat::Ushort4Norm,
+            Self::Short2Norm => wgpu::VertexFormat::Short2Norm,
+            Self::Short4Norm => wgpu::VertexFormat::Short4Norm,
+            Self::Half2 => wgpu::VertexFormat::Half2,
+            Self::Half4 => wgpu::VertexFormat::Half4,
+            Self::Float => wgpu::VertexFormat::Float,
+            Self::Float2 => wgpu::VertexFormat::Float2,
+            Self::Float3 => wgpu::VertexFormat::Float3,
+            Self::Float4 => wgpu::VertexFormat::Float4,
+            Self::UInt => wgpu::VertexFormat::Uint,
+            Self::UInt2 => wgpu::VertexFormat::Uint2,
+            Self::UInt3 => wgpu::VertexFormat::Uint3,
+            Self::UInt4 => wgpu::VertexFormat::Uint4,
+            Self::Int => wgpu::VertexFormat::Int,
+            Self::Int2 => wgpu::VertexFormat::Int2,
+            Self::Int3 => wgpu::VertexFormat::Int3,
+            Self::Int4 => wgpu::VertexFormat::Int4,
+        }
+    }
+    /// The scalar type and number of components seen by the shader.
+    fn shader_type(self) -> (reflect::Scalar, u32) {
+        match self {
+            Self::UChar2 => (reflect::Scalar::Uint, 2),
+            Self::UChar4 => (reflect::Scalar::Uint, 4),
+            Self::Char2 => (reflect::Scalar::Sint, 2),
+            Self::Char4 => (reflect::Scalar::Sint, 4),
+            Self::UChar2Norm => (reflect::Scalar::Float, 2),
+            Self::UChar4Norm | Self::UByte4 => (reflect::Scalar::Float, 4),
+            Self::Char2Norm => (reflect::Scalar::Float, 2),
+            Self::Char4Norm => (reflect::Scalar::Float, 4),
+            Self::UShort2 => (reflect::Scalar::Uint, 2),
+            Self::UShort4 => (reflect::Scalar::Uint, 4),
+            Self::Short2 => (reflect::Scalar::Sint, 2),
+            Self::Short4 => (reflect::Scalar::Sint, 4),
+            Self::UShort2Norm => (reflect::Scalar::Float, 2),
+            Self::UShort4Norm => (reflect::Scalar::Float, 4),
+            Self::Short2Norm => (reflect::Scalar::Float, 2),
+            Self::Short4Norm => (reflect::Scalar::Float, 4),
+            Self::Half2 => (reflect::Scalar::Float, 2),
+            Self::Half4 => (reflect::Scalar::Float, 4),
+            Self::Float => (reflect::Scalar::Float, 1),
+            Self::Float2 => (reflect::Scalar::Float, 2),
+            Self::Float3 => (reflect::Scalar::Float, 3),
+            Self::Float4 => (reflect::Scalar::Float, 4),
+            Self::UInt => (reflect::Scalar::Uint, 1),
+            Self::UInt2 => (reflect::Scalar::Uint, 2),
+            Self::UInt3 => (reflect::Scalar::Uint, 3),
+            Self::UInt4 => (reflect::Scalar::Uint, 4),
+            Self::Int => (reflect::Scalar::Sint, 1),
+            Self::Int2 => (reflect::Scalar::Sint, 2),
+            Self::Int3 => (reflect::Scalar::Sint, 3),
+            Self::Int4 => (reflect::Scalar::Sint, 4),
+        }
+    }
+}
+
+/// How often a vertex buffer advances to its next element.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StepMode {
+    /// Once per vertex.
+    #[default]
+    Vertex,
+    /// Once per instance, for instanced draws.
+    Instance,
+}
+
+impl StepMode {
+    fn to_wgpu(self) -> wgpu::InputStepMode {
+        match self {
+            Self::Vertex => wgpu::InputStepMode::Vertex,
+            Self::Instance => wgpu::InputStepMode::Instance,
+        }
+    }
+}
+
+/// The layout of a pipeline's vertex buffer slot. The attributes of all
+/// slots are read by shaders at consecutive locations, eg. with two
+/// attributes in slot `0`, the first attribute of slot `1` is at location
+/// `2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexBufferLayout<'a> {
+    pub formats: &'a [VertexFormat],
+    pub step_mode: StepMode,
+}
+
+/// An owned `VertexBufferLayout`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VertexBufferDescriptor {
+    pub formats: Vec<VertexFormat>,
+    pub step_mode: StepMode,
+}
+
+impl VertexBufferDescriptor {
+    fn layout(&self) -> VertexBufferLayout<'_> {
+        VertexBufferLayout {
+            formats: &self.formats,
+            step_mode: self.step_mode,
+        }
+    }
+}
+
+impl<'a> From<VertexBufferLayout<'a>> for VertexBufferDescriptor {
+    fn from(layout: VertexBufferLayout<'a>) -> Self {
+        Self {
+            formats: layout.formats.to_vec(),
+            step_mode: layout.step_mode,
+        }
+    }
+}
+
+/// Describes a 'VertexBuffer' layout.
+#[derive(Default)]
+pub struct VertexLayout {
+    wgpu_attrs: Vec<wgpu::VertexAttributeDescriptor>,
+    formats: Vec<VertexFormat>,
+    size: usize,
+    step_mode: StepMode,
+}
+
+impl VertexLayout {
+    pub fn from(formats: &[VertexFormat]) -> Self {
+        Self::new(
+            VertexBufferLayout {
+                formats,
+                step_mode: StepMode::Vertex,
+            },
+            0,
+        )
+    }
+
+    /// Create a layout with attributes at consecutive shader locations,
+    /// starting from the given one.
+    fn new(layout: VertexBufferLayout, location: u32) -> Self {
+        let mut vl = Self {
+            step_mode: layout.step_mode,
+            ..Self::default()
+        };
+        for vf in layout.formats {
+            vl.formats.push(*vf);
+            vl.wgpu_attrs.push(wgpu::VertexAttributeDescriptor {
+                shader_location: location + vl.wgpu_attrs.len() as u32,
+                offset: vl.size as wgpu::BufferAddress,
+                format: vf.to_wgpu(),
+            });
+            vl.size += vf.bytesize();
+        }
+        vl
+    }
+
+    fn to_wgpu(&self) -> wgpu::VertexBufferDescriptor {
+        wgpu::VertexBufferDescriptor {
+            stride: self.size as wgpu::BufferAddress,
+            step_mode: self.step_mode.to_wgpu(),
+            attributes: self.wgpu_attrs.as_slice(),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Pipeline Bindings
+///////////////////////////////////////////////////////////////////////////////
+
+/// A binding type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingType {
+    UniformBuffer,
+    UniformBufferDynamic,
+    Sampler,
+    SampledTexture,
+    /// A `StorageBuffer`, readable and writable by shaders.
+    StorageBuffer,
+    /// A `Texture` created with the `STORAGE` usage, readable and writable
+    /// by shaders.
+    StorageTexture,
 }
 
 impl BindingType {
-    fn to_wgpu(&self) -> wgpu::BindingType {
+    fn to_wgpu(self) -> wgpu::BindingType {
         match self {
             BindingType::UniformBuffer => wgpu::BindingType::UniformBuffer { dynamic: false },
             BindingType::UniformBufferDynamic => wgpu::BindingType::UniformBuffer { dynamic: true },
@@ -1163,10 +2244,32 @@ impl BindingType {
                 dimension: wgpu::TextureViewDimension::D2,
             },
             BindingType::Sampler => wgpu::BindingType::Sampler,
+            BindingType::StorageBuffer => wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                readonly: false,
+            },
+            BindingType::StorageTexture => wgpu::BindingType::StorageTexture {
+                dimension: wgpu::TextureViewDimension::D2,
+            },
+        }
+    }
+
+    /// Whether a shader resource of the given kind can be bound with this
+    /// binding type.
+    fn matches(self, kind: reflect::Kind) -> bool {
+        match self {
+            Self::UniformBuffer | Self::UniformBufferDynamic => {
+                kind == reflect::Kind::UniformBuffer
+            }
+            Self::Sampler => kind == reflect::Kind::Sampler,
+            Self::SampledTexture => kind == reflect::Kind::SampledTexture,
+            Self::StorageBuffer => kind == reflect::Kind::StorageBuffer,
+            Self::StorageTexture => kind == reflect::Kind::StorageTexture,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Binding {
     pub binding: BindingType,
     pub stage: ShaderStage,
@@ -1176,16 +2279,30 @@ pub struct Binding {
 /// Pipeline
 ///////////////////////////////////////////////////////////////////////////////
 
-pub struct Pipeline {
+/// A render pipeline drawing vertices of type `V` from its first vertex
+/// buffer slot.
+pub struct Pipeline<V> {
     wgpu: wgpu::RenderPipeline,
 
     pub layout: PipelineLayout,
-    pub vertex_layout: VertexLayout,
+    /// The layout of each vertex buffer slot.
+    pub vertex_layout: Vec<VertexLayout>,
+
+    index_format: IndexFormat,
+    vertex: PhantomData<V>,
+}
+
+impl<V> Pipeline<V> {
+    /// The format of index buffers drawn with the pipeline.
+    pub fn index_format(&self) -> IndexFormat {
+        self.index_format
+    }
 }
 
-impl<'a> AbstractPipeline<'a> for Pipeline {
+impl<'a, V: Vertex> AbstractPipeline<'a> for Pipeline<V> {
     type PrepareContext = ();
     type Uniforms = ();
+    type Vertex = V;
 
     fn description() -> PipelineDescription<'a> {
         PipelineDescription {
@@ -1193,6 +2310,7 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
             pipeline_layout: &[],
             vertex_shader: &[],
             fragment_shader: &[],
+            index_format: IndexFormat::Uint16,
         }
     }
 
@@ -1202,6 +2320,12 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
 
     fn apply(&self, pass: &mut Pass) {
         pass.wgpu.set_pipeline(&self.wgpu);
+        pass.index_format = Some(self.index_format);
+        pass.vertex_formats = self
+            .vertex_layout
+            .iter()
+            .map(|l| l.formats.clone())
+            .collect();
     }
 
     fn width(&self) -> u32 {
@@ -1219,6 +2343,17 @@ impl<'a> AbstractPipeline<'a> for Pipeline {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// Compute Pipeline
+///////////////////////////////////////////////////////////////////////////////
+
+/// A pipeline running a compute shader, used in a `ComputePass`.
+pub struct ComputePipeline {
+    wgpu: wgpu::ComputePipeline,
+
+    pub layout: PipelineLayout,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Blending {
     src_factor: BlendFactor,
@@ -1296,6 +2431,213 @@ impl BlendOp {
 
 pub struct Set<'a>(pub &'a [Binding]);
 
+/// A mismatch between a pipeline's vertex or pipeline layout and its
+/// shaders, found when creating the pipeline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PipelineError {
+    /// The vertex shader has an input with no vertex attribute at its
+    /// location.
+    MissingAttribute { location: u32, name: String },
+    /// A vertex attribute's format doesn't match the type of the vertex
+    /// shader input at its location.
+    AttributeMismatch {
+        location: u32,
+        name: String,
+        format: VertexFormat,
+        shader_type: String,
+    },
+    /// A shader uses a binding which isn't in the pipeline layout.
+    MissingBinding {
+        stage: ShaderStage,
+        set: u32,
+        binding: u32,
+        name: String,
+    },
+    /// A binding's type doesn't match how it's used by a shader.
+    BindingMismatch {
+        stage: ShaderStage,
+        set: u32,
+        binding: u32,
+        name: String,
+        binding_type: BindingType,
+        shader_type: String,
+    },
+    /// A binding isn't visible to a shader stage using it.
+    StageMismatch {
+        stage: ShaderStage,
+        set: u32,
+        binding: u32,
+        name: String,
+        visibility: ShaderStage,
+    },
+    /// The first vertex buffer slot doesn't have the layout of the
+    /// pipeline's vertex type.
+    VertexTypeMismatch { vertex_type: String },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Shaders compiled without debug info have no variable names.
+        let named = |name: &str| {
+            if name.is_empty() {
+                String::new()
+            } else {
+                format!(" `{}`", name)
+            }
+        };
+        match self {
+            Self::MissingAttribute { location, name } => write!(
+                f,
+                "vertex shader input{} at location {} has no vertex attribute",
+                named(name),
+                location
+            ),
+            Self::AttributeMismatch {
+                location,
+                name,
+                format,
+                shader_type,
+            } => write!(
+                f,
+                "vertex attribute {} has format {:?}, but vertex shader input{} is a `{}`",
+                location,
+                format,
+                named(name),
+                shader_type
+            ),
+            Self::MissingBinding {
+                stage,
+                set,
+                binding,
+                name,
+            } => write!(
+                f,
+                "{:?} shader uses{} at set {}, binding {}, which isn't in the pipeline layout",
+                stage,
+                named(name),
+                set,
+                binding
+            ),
+            Self::BindingMismatch {
+                stage,
+                set,
+                binding,
+                name,
+                binding_type,
+                shader_type,
+            } => write!(
+                f,
+                "set {}, binding {} is a {:?}, but {:?} shader uses it{} as a {}",
+                set,
+                binding,
+                binding_type,
+                stage,
+                named(name),
+                shader_type
+            ),
+            Self::StageMismatch {
+                stage,
+                set,
+                binding,
+                name,
+                visibility,
+            } => write!(
+                f,
+                "set {}, binding {} is only visible to the {:?} stage, but {:?} shader uses it{}",
+                set,
+                binding,
+                visibility,
+                stage,
+                named(name)
+            ),
+            Self::VertexTypeMismatch { vertex_type } => write!(
+                f,
+                "vertex buffer slot 0 doesn't have the layout of the vertex type `{}`",
+                vertex_type
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Check a vertex and pipeline layout against the inputs and bindings of
+/// the given shaders. Vertex attributes and bindings which aren't used by
+/// the shaders are allowed.
+fn validate_pipeline(
+    vertex_layout: &[VertexFormat],
+    sets: &[Set],
+    shaders: &[&Shader],
+) -> Result<(), PipelineError> {
+    for shader in shaders {
+        let interface = match &shader.interface {
+            Some(interface) => interface,
+            None => continue,
+        };
+
+        if shader.stage == ShaderStage::Vertex {
+            for input in &interface.inputs {
+                let format = match vertex_layout.get(input.location as usize) {
+                    Some(format) => *format,
+                    None => {
+                        return Err(PipelineError::MissingAttribute {
+                            location: input.location,
+                            name: input.name.clone(),
+                        })
+                    }
+                };
+                let (scalar, components) = format.shader_type();
+
+                if scalar != input.scalar || components < input.components {
+                    return Err(PipelineError::AttributeMismatch {
+                        location: input.location,
+                        name: input.name.clone(),
+                        format,
+                        shader_type: input.type_name(),
+                    });
+                }
+            }
+        }
+
+        for res in &interface.resources {
+            let binding = match sets
+                .get(res.set as usize)
+                .and_then(|s| s.0.get(res.binding as usize))
+            {
+                Some(binding) => binding,
+                None => {
+                    return Err(PipelineError::MissingBinding {
+                        stage: shader.stage,
+                        set: res.set,
+                        binding: res.binding,
+                        name: res.name.clone(),
+                    })
+                }
+            };
+            if !binding.binding.matches(res.kind) {
+                return Err(PipelineError::BindingMismatch {
+                    stage: shader.stage,
+                    set: res.set,
+                    binding: res.binding,
+                    name: res.name.clone(),
+                    binding_type: binding.binding,
+                    shader_type: res.kind.to_string(),
+                });
+            }
+            if binding.stage != shader.stage {
+                return Err(PipelineError::StageMismatch {
+                    stage: shader.stage,
+                    set: res.set,
+                    binding: res.binding,
+                    name: res.name.clone(),
+                    visibility: binding.stage,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 pub struct PipelineLayout {
     pub sets: Vec<BindingGroupLayout>,
 }
@@ -1303,9 +2645,13 @@ pub struct PipelineLayout {
 pub trait AbstractPipeline<'a> {
     type PrepareContext;
     type Uniforms: Copy + 'static;
+    /// The type of the vertices drawn by the pipeline. The first slot of the
+    /// description's vertex layout must be this type's `LAYOUT`, and only
+    /// buffers of this type can be drawn with the pipeline.
+    type Vertex: Vertex;
 
     fn description() -> PipelineDescription<'a>;
-    fn setup(pip: Pipeline, dev: &Device, w: u32, h: u32) -> Self;
+    fn setup(pip: Pipeline<Self::Vertex>, dev: &Device, w: u32, h: u32) -> Self;
     fn apply(&self, pass: &mut Pass);
     fn resize(&mut self, w: u32, h: u32);
     fn width(&self) -> u32;
@@ -1317,10 +2663,65 @@ pub trait AbstractPipeline<'a> {
 }
 
 pub struct PipelineDescription<'a> {
-    pub vertex_layout: &'a [VertexFormat],
+    /// The layout of each vertex buffer slot, usually the `LAYOUT` of a
+    /// `Vertex` type.
+    pub vertex_layout: &'a [VertexBufferLayout<'a>],
     pub pipeline_layout: &'a [Set<'a>],
     pub vertex_shader: &'static [u8],
     pub fragment_shader: &'static [u8],
+    /// The format of index buffers drawn with the pipeline.
+    pub index_format: IndexFormat,
+}
+
+/// An owned pipeline description, which unlike `PipelineDescription` can be
+/// built from runtime data, such as shaders compiled or loaded at runtime.
+/// It also specifies the blending and target format of the pipeline, so
+/// that one pipeline type can be instantiated with different settings.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::*;
+/// use rgx::kit::sprite2d;
+///
+/// let mut desc = PipelineDescriptor::from(sprite2d::Pipeline::description());
+/// desc.blending = Blending::constant();
+/// desc.format = TextureFormat::Rgba16Float;
+/// desc.index_format = IndexFormat::Uint32;
+///
+/// assert_eq!(desc.vertex_layout[0].formats.len(), 4);
+/// assert_eq!(desc.pipeline_layout.len(), 3);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineDescriptor {
+    /// The layout of each vertex buffer slot.
+    pub vertex_layout: Vec<VertexBufferDescriptor>,
+    /// The bindings of each set.
+    pub pipeline_layout: Vec<Vec<Binding>>,
+    /// SPIR-V vertex shader.
+    pub vertex_shader: Vec<u8>,
+    /// SPIR-V fragment shader.
+    pub fragment_shader: Vec<u8>,
+    pub blending: Blending,
+    pub format: TextureFormat,
+    /// The format of index buffers drawn with the pipeline.
+    pub index_format: IndexFormat,
+}
+
+impl<'a> From<PipelineDescription<'a>> for PipelineDescriptor {
+    /// Create an owned description with the default blending, rendering to
+    /// the swap chain.
+    fn from(desc: PipelineDescription<'a>) -> Self {
+        Self {
+            vertex_layout: desc.vertex_layout.iter().map(|&l| l.into()).collect(),
+            pipeline_layout: desc.pipeline_layout.iter().map(|s| s.0.to_vec()).collect(),
+            vertex_shader: desc.vertex_shader.to_vec(),
+            fragment_shader: desc.fragment_shader.to_vec(),
+            blending: Blending::default(),
+            format: TextureFormat::Bgra8,
+            index_format: desc.index_format,
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -1340,6 +2741,12 @@ impl Frame {
         Pass::begin(&mut self.encoder, &view.texture_view(), op)
     }
 
+    pub fn compute_pass(&mut self) -> ComputePass {
+        ComputePass {
+            wgpu: self.encoder.begin_compute_pass(),
+        }
+    }
+
     pub fn copy(&mut self, src: &UniformBuffer, dst: &UniformBuffer) {
         self.encoder.copy_buffer_to_buffer(
             &src.wgpu,
@@ -1365,6 +2772,10 @@ impl Frame {
 
 pub struct Pass<'a> {
     wgpu: wgpu::RenderPass<'a>,
+    /// Index format of the current pipeline.
+    index_format: Option<IndexFormat>,
+    /// Vertex formats of each slot of the current pipeline.
+    vertex_formats: Vec<Vec<VertexFormat>>,
 }
 
 impl<'a> Pass<'a> {
@@ -1386,37 +2797,151 @@ impl<'a> Pass<'a> {
             }],
             depth_stencil_attachment: None,
         });
-        Pass { wgpu: pass }
+        Pass {
+            wgpu: pass,
+            index_format: None,
+            vertex_formats: Vec::new(),
+        }
     }
-    pub fn set_pipeline<T>(&mut self, pipeline: &T)
+    /// Set the pipeline, returning a pass which can draw the pipeline's
+    /// vertex type.
+    pub fn set_pipeline<T>(&mut self, pipeline: &T) -> PipelinePass<'_, 'a, T::Vertex>
     where
         T: AbstractPipeline<'a>,
     {
         pipeline.apply(self);
+
+        PipelinePass {
+            pass: self,
+            vertex: PhantomData,
+        }
     }
     pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
         self.wgpu
             .set_bind_group(group.set_index, &group.wgpu, offsets);
     }
+    /// Bind an index buffer. Its format must match the pipeline's.
     pub fn set_index_buffer(&mut self, index_buf: &IndexBuffer) {
+        if let Some(format) = self.index_format {
+            assert_eq!(
+                index_buf.format, format,
+                "Pass::set_index_buffer: index format doesn't match the pipeline's"
+            );
+        }
         self.wgpu.set_index_buffer(&index_buf.wgpu, 0)
     }
-    pub fn set_vertex_buffer(&mut self, vertex_buf: &VertexBuffer) {
-        self.wgpu.set_vertex_buffers(0, &[(&vertex_buf.wgpu, 0)])
+    pub fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
+        self.wgpu.draw_indexed(indices, 0, instances)
+    }
+    /// Use a storage buffer as the vertex buffer, eg. after writing to it
+    /// in a compute pass.
+    pub fn set_storage_vertex_buffer(&mut self, slot: u32, storage_buf: &StorageBuffer) {
+        self.wgpu
+            .set_vertex_buffers(slot, &[(&storage_buf.wgpu, 0)])
+    }
+    /// Draw all the elements of a storage buffer as vertices.
+    pub fn draw_storage_buffer(&mut self, buf: &StorageBuffer) {
+        self.set_storage_vertex_buffer(0, buf);
+        self.wgpu.draw(0..buf.count as u32, 0..1);
     }
-    pub fn draw<T: Draw>(&mut self, drawable: &T, binding: &BindingGroup) {
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// PipelinePass
+///////////////////////////////////////////////////////////////////////////////
+
+/// A render pass with a pipeline set, returned by `Pass::set_pipeline`. Only
+/// vertex buffers of the pipeline's vertex type `V` can be drawn with it, so
+/// that a buffer of the wrong type is a compile error. Buffers bound to
+/// other slots are checked against the pipeline when they're bound.
+/// Dereferences to the underlying `Pass`.
+///
+/// # Examples
+///
+/// ```compile_fail
+/// use rgx::core::{Pass, VertexBuffer};
+/// use rgx::kit::{shape2d, sprite2d};
+///
+/// fn draw(pass: &mut Pass, sprites: &sprite2d::Pipeline, buf: &VertexBuffer<shape2d::Vertex>) {
+///     // Shape vertices can't be drawn with the sprite pipeline.
+///     pass.set_pipeline(sprites).draw_buffer(buf);
+/// }
+/// ```
+pub struct PipelinePass<'p, 'a, V> {
+    pass: &'p mut Pass<'a>,
+    vertex: PhantomData<V>,
+}
+
+impl<'p, 'a, V: Vertex> PipelinePass<'p, 'a, V> {
+    /// Bind a vertex buffer to a slot of the pipeline's vertex layout, eg. a
+    /// per-instance buffer. The formats of the buffer's vertex type must
+    /// match the ones of the slot.
+    pub fn set_vertex_buffer<W: Vertex>(&mut self, slot: u32, vertex_buf: &VertexBuffer<W>) {
+        let formats = self.pass.vertex_formats.get(slot as usize);
+
+        assert!(
+            formats.map(Vec::as_slice) == Some(W::FORMATS),
+            "PipelinePass::set_vertex_buffer: `{}` doesn't match the layout of slot {}",
+            std::any::type_name::<W>(),
+            slot
+        );
+        self.pass
+            .wgpu
+            .set_vertex_buffers(slot, &[(&vertex_buf.wgpu, 0)])
+    }
+    pub fn draw<T: Draw<Vertex = V>>(&mut self, drawable: &T, binding: &BindingGroup) {
         drawable.draw(binding, self);
     }
-    pub fn draw_buffer(&mut self, buf: &VertexBuffer) {
-        self.set_vertex_buffer(buf);
-        self.wgpu.draw(0..buf.size, 0..1);
+    pub fn draw_buffer(&mut self, buf: &VertexBuffer<V>) {
+        self.set_vertex_buffer(0, buf);
+        self.pass.wgpu.draw(0..buf.size, 0..1);
     }
-    pub fn draw_buffer_range(&mut self, buf: &VertexBuffer, range: Range<u32>) {
-        self.set_vertex_buffer(buf);
-        self.wgpu.draw(range, 0..1);
+    pub fn draw_buffer_range(&mut self, buf: &VertexBuffer<V>, range: Range<u32>) {
+        self.set_vertex_buffer(0, buf);
+        self.pass.wgpu.draw(range, 0..1);
     }
-    pub fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
-        self.wgpu.draw_indexed(indices, 0, instances)
+    /// Draw the vertices of the given buffer, bound to slot `0`, once for
+    /// each instance. Per-instance buffers must be bound to their slots
+    /// beforehand.
+    pub fn draw_instanced(&mut self, buf: &VertexBuffer<V>, instances: Range<u32>) {
+        self.set_vertex_buffer(0, buf);
+        self.pass.wgpu.draw(0..buf.size, instances);
+    }
+}
+
+impl<'p, 'a, V> Deref for PipelinePass<'p, 'a, V> {
+    type Target = Pass<'a>;
+
+    fn deref(&self) -> &Pass<'a> {
+        self.pass
+    }
+}
+
+impl<'p, 'a, V> DerefMut for PipelinePass<'p, 'a, V> {
+    fn deref_mut(&mut self) -> &mut Pass<'a> {
+        self.pass
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// ComputePass
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct ComputePass<'a> {
+    wgpu: wgpu::ComputePass<'a>,
+}
+
+impl<'a> ComputePass<'a> {
+    pub fn set_pipeline(&mut self, pipeline: &ComputePipeline) {
+        self.wgpu.set_pipeline(&pipeline.wgpu);
+    }
+    pub fn set_binding(&mut self, group: &BindingGroup, offsets: &[u64]) {
+        self.wgpu
+            .set_bind_group(group.set_index, &group.wgpu, offsets);
+    }
+    /// Run the compute shader over a grid of `x * y * z` work groups.
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.wgpu.dispatch(x, y, z);
     }
 }
 
@@ -1479,6 +3004,7 @@ pub struct SwapChain {
     pub width: u32,
     pub height: u32,
 
+    format: TextureFormat,
     wgpu: wgpu::SwapChain,
 }
 
@@ -1489,157 +3015,599 @@ impl SwapChain {
         (self.width, self.height)
     }
 
-    /// Returns the next texture to be presented by the swapchain for drawing.
+    /// Returns the next texture to be presented by the swapchain for drawing.
+    ///
+    /// When the [`SwapChainTexture`] returned by this method is dropped, the
+    /// swapchain will present the texture to the associated [`Renderer`].
+    pub fn next(&mut self) -> SwapChainTexture {
+        SwapChainTexture(self.wgpu.get_next_texture())
+    }
+
+    /// Get the texture format in use. Pipelines rendering to the swap chain
+    /// must be created with this format.
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    fn descriptor(
+        width: u32,
+        height: u32,
+        mode: PresentMode,
+        format: TextureFormat,
+    ) -> wgpu::SwapChainDescriptor {
+        assert!(
+            format == TextureFormat::Bgra8 || format == TextureFormat::Bgra8Srgb,
+            "SwapChain: format must be `Bgra8` or `Bgra8Srgb`, got `{:?}`",
+            format
+        );
+        wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            format: format.to_wgpu(),
+            present_mode: mode.to_wgpu(),
+            width,
+            height,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Renderer
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct Renderer {
+    pub device: Device,
+}
+
+impl Renderer {
+    pub fn new(window: RawWindowHandle) -> Self {
+        Self::with_extensions(window, Extensions::default())
+    }
+
+    /// Create a renderer with the given device extensions enabled. The
+    /// adapter must support them.
+    pub fn with_extensions(window: RawWindowHandle, extensions: Extensions) -> Self {
+        Self {
+            device: Device::with_extensions(window, extensions),
+        }
+    }
+
+    pub fn swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> SwapChain {
+        self.swap_chain_with(w, h, mode, TextureFormat::Bgra8)
+    }
+
+    /// Create a swap chain of the given format, which must be either
+    /// `TextureFormat::Bgra8` or `TextureFormat::Bgra8Srgb`. With the latter,
+    /// linear colors written by fragment shaders are encoded to sRGB when
+    /// presented.
+    pub fn swap_chain_with(
+        &self,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+        format: TextureFormat,
+    ) -> SwapChain {
+        SwapChain {
+            wgpu: self.device.create_swap_chain_with(w, h, mode, format),
+            format,
+            width: w,
+            height: h,
+        }
+    }
+
+    pub fn texture(&self, w: u32, h: u32) -> Texture {
+        self.device.create_texture(w, h)
+    }
+
+    pub fn texture_with(&self, w: u32, h: u32, opts: TextureOptions) -> Texture {
+        self.device.create_texture_with(w, h, opts)
+    }
+
+    pub fn framebuffer(&self, w: u32, h: u32) -> Framebuffer {
+        self.device.create_framebuffer(w, h)
+    }
+
+    pub fn framebuffer_with(&self, w: u32, h: u32, format: TextureFormat) -> Framebuffer {
+        self.device.create_framebuffer_with(w, h, format)
+    }
+
+    pub fn vertex_buffer<V: Vertex>(&self, verts: &[V]) -> VertexBuffer<V> {
+        self.device.create_buffer(verts)
+    }
+
+    pub fn index_buffer<I: IndexType>(&self, indices: &[I]) -> IndexBuffer {
+        self.device.create_index(indices)
+    }
+
+    pub fn indexed_mesh<V: Vertex, I: IndexType>(
+        &self,
+        verts: &[V],
+        indices: &[I],
+    ) -> IndexedMesh<V> {
+        IndexedMesh::new(self.vertex_buffer(verts), self.index_buffer(indices))
+    }
+
+    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_uniform_buffer(buf)
+    }
+
+    pub fn storage_buffer<T>(&self, buf: &[T]) -> StorageBuffer
+    where
+        T: 'static + Copy,
+    {
+        self.device.create_storage_buffer(buf)
+    }
+
+    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
+        self.device.create_binding_group(layout, binds)
+    }
+
+    pub fn sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
+        self.device.create_sampler(min_filter, mag_filter)
+    }
+
+    pub fn sampler_with(&self, desc: &SamplerDescriptor) -> Sampler {
+        self.device.create_sampler_with(desc)
+    }
+
+    pub fn pipeline<T>(&self, w: u32, h: u32, blending: Blending) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.pipeline_with(w, h, blending, TextureFormat::Bgra8)
+    }
+
+    /// Create a pipeline rendering to targets of the given format. Pipelines
+    /// rendering to the swap chain must use the swap chain's format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline's description doesn't match its shaders.
+    pub fn pipeline_with<T>(&self, w: u32, h: u32, blending: Blending, format: TextureFormat) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+        let vs =
+            self.device
+                .create_shader("vertex shader", desc.vertex_shader, ShaderStage::Vertex);
+        let fs = self.device.create_shader(
+            "fragment shader",
+            desc.fragment_shader,
+            ShaderStage::Fragment,
+        );
+
+        self.try_pipeline_with_shaders(w, h, blending, format, &vs, &fs)
+            .unwrap_or_else(|err| panic!("Renderer::pipeline_with: {}", err))
+    }
+
+    /// Create a pipeline from a runtime description, instead of the one
+    /// returned by `AbstractPipeline::description`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the description doesn't match its shaders. See
+    /// `Renderer::try_pipeline_from`.
+    pub fn pipeline_from<T>(&self, w: u32, h: u32, desc: &PipelineDescriptor) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.try_pipeline_from(w, h, desc)
+            .unwrap_or_else(|err| panic!("Renderer::pipeline_from: {}", err))
+    }
+
+    /// Create a pipeline from a runtime description, checking that its
+    /// vertex and pipeline layouts match the inputs and bindings of its
+    /// shaders.
+    pub fn try_pipeline_from<T>(
+        &self,
+        w: u32,
+        h: u32,
+        desc: &PipelineDescriptor,
+    ) -> Result<T, PipelineError>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let sets: Vec<Set> = desc.pipeline_layout.iter().map(|s| Set(s)).collect();
+        let vertex_layout: Vec<VertexBufferLayout> =
+            desc.vertex_layout.iter().map(|l| l.layout()).collect();
+        let vs =
+            self.device
+                .create_shader("vertex shader", &desc.vertex_shader, ShaderStage::Vertex);
+        let fs = self.device.create_shader(
+            "fragment shader",
+            &desc.fragment_shader,
+            ShaderStage::Fragment,
+        );
+        let pipeline = self.device.create_pipeline(
+            &sets,
+            &vertex_layout,
+            desc.blending.clone(),
+            desc.format,
+            desc.index_format,
+            &vs,
+            &fs,
+        )?;
+
+        Ok(T::setup(pipeline, &self.device, w, h))
+    }
+
+    /// Create a pipeline with the given shaders instead of the ones from its
+    /// description, eg. with shaders compiled at runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline's description doesn't match the shaders. See
+    /// `Renderer::try_pipeline_with_shaders`.
+    pub fn pipeline_with_shaders<T>(
+        &self,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        format: TextureFormat,
+        vs: &Shader,
+        fs: &Shader,
+    ) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        self.try_pipeline_with_shaders(w, h, blending, format, vs, fs)
+            .unwrap_or_else(|err| panic!("Renderer::pipeline_with_shaders: {}", err))
+    }
+
+    /// Create a pipeline with the given shaders, checking that the vertex
+    /// and pipeline layouts of its description match the inputs and bindings
+    /// of the shaders.
+    pub fn try_pipeline_with_shaders<T>(
+        &self,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        format: TextureFormat,
+        vs: &Shader,
+        fs: &Shader,
+    ) -> Result<T, PipelineError>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+        let pipeline = self.device.create_pipeline(
+            desc.pipeline_layout,
+            desc.vertex_layout,
+            blending,
+            format,
+            desc.index_format,
+            vs,
+            fs,
+        )?;
+
+        Ok(T::setup(pipeline, &self.device, w, h))
+    }
+
+    /// Create a compute pipeline from a pipeline layout and SPIR-V compute
+    /// shader.
     ///
-    /// When the [`SwapChainTexture`] returned by this method is dropped, the
-    /// swapchain will present the texture to the associated [`Renderer`].
-    pub fn next(&mut self) -> SwapChainTexture {
-        SwapChainTexture(self.wgpu.get_next_texture())
+    /// # Panics
+    ///
+    /// Panics if the pipeline layout doesn't match the shader. See
+    /// `Device::create_compute_pipeline`.
+    pub fn compute_pipeline(&self, pipeline_layout: &[Set], shader: &[u8]) -> ComputePipeline {
+        let cs = self
+            .device
+            .create_shader("compute shader", shader, ShaderStage::Compute);
+
+        self.device
+            .create_compute_pipeline(pipeline_layout, &cs)
+            .unwrap_or_else(|err| panic!("Renderer::compute_pipeline: {}", err))
     }
 
-    /// Get the texture format in use
-    pub fn format(&self) -> wgpu::TextureFormat {
-        wgpu::TextureFormat::Bgra8Unorm
+    /// Read the contents of a framebuffer, and pass them to `f` as BGRA
+    /// bytes. Blocks until the GPU is done.
+    pub fn read<F>(&mut self, fb: &Framebuffer, f: F)
+    where
+        F: FnOnce(&[u8]),
+    {
+        let pixels = self.read_pixels(fb);
+        // Safe because `Bgra8` is `repr(C)` and made of four `u8`s.
+        let bytes =
+            unsafe { std::slice::from_raw_parts(pixels.as_ptr() as *const u8, pixels.len() * 4) };
+
+        f(bytes);
     }
 
-    fn descriptor(width: u32, height: u32, mode: PresentMode) -> wgpu::SwapChainDescriptor {
-        wgpu::SwapChainDescriptor {
-            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8Unorm,
-            present_mode: mode.to_wgpu(),
-            width,
-            height,
-        }
+    /// Read the contents of a framebuffer into host memory, blocking until
+    /// the GPU is done. Rows are ordered from top to bottom.
+    pub fn read_pixels(&mut self, fb: &Framebuffer) -> Vec<Bgra8> {
+        self.read_pixels_rect(fb, Rect::origin(fb.width(), fb.height()))
     }
-}
 
-///////////////////////////////////////////////////////////////////////////////
-/// Renderer
-///////////////////////////////////////////////////////////////////////////////
+    /// Read a region of a framebuffer into host memory, blocking until the
+    /// GPU is done. Like with `Canvas::transfer`, the region is given with
+    /// the Y axis pointing up. Rows are ordered from top to bottom.
+    pub fn read_pixels_rect(&mut self, fb: &Framebuffer, rect: Rect<u32>) -> Vec<Bgra8> {
+        let future = self.read_pixels_async(fb, rect);
+        self.wait(future)
+    }
 
-pub struct Renderer {
-    pub device: Device,
-}
+    /// Read a region of a framebuffer into host memory, returning a future
+    /// which resolves once the GPU is done. The future only makes progress
+    /// when the device is polled, eg. with `Renderer::poll`, or when commands
+    /// are submitted.
+    pub fn read_pixels_async(&mut self, fb: &Framebuffer, rect: Rect<u32>) -> ReadPixels<Bgra8> {
+        self.read_async(&fb.texture, 0, 0, rect)
+    }
 
-impl Renderer {
-    pub fn new(window: RawWindowHandle) -> Self {
-        Self {
-            device: Device::new(window),
-        }
+    /// Read the contents of a texture into host memory, blocking until the
+    /// GPU is done. The texture must have been created with the `COPY_SRC`
+    /// usage.
+    pub fn read_texture(&mut self, texture: &Texture) -> Vec<Rgba8> {
+        self.read_texture_level(texture, 0, 0, Rect::origin(texture.w, texture.h))
     }
 
-    pub fn swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> SwapChain {
-        SwapChain {
-            wgpu: self.device.create_swap_chain(w, h, mode),
-            width: w,
-            height: h,
+    /// Read a region of the given mip level and array layer of a texture,
+    /// blocking until the GPU is done. The region is given in the mip level's
+    /// coordinates, with the Y axis pointing up. The texel type must match
+    /// the texture format.
+    pub fn read_texture_level<T: Pixel>(
+        &mut self,
+        texture: &Texture,
+        level: u32,
+        layer: u32,
+        rect: Rect<u32>,
+    ) -> Vec<T> {
+        let future = self.read_texture_async(texture, level, layer, rect);
+        self.wait(future)
+    }
+
+    /// Like `Renderer::read_texture_level`, but returns a future instead of
+    /// blocking.
+    pub fn read_texture_async<T: Pixel>(
+        &mut self,
+        texture: &Texture,
+        level: u32,
+        layer: u32,
+        rect: Rect<u32>,
+    ) -> ReadPixels<T> {
+        self.read_async(texture, level, layer, rect)
+    }
+
+    /// Generate the mip chain of a texture from its base level, by
+    /// downsampling each level into the next on the GPU. The texture must
+    /// have been created with the `OUTPUT_ATTACHMENT` usage, as is the case
+    /// with `TextureOptions::mipmapped`.
+    pub fn generate_mipmaps(&mut self, texture: &Texture) {
+        assert!(
+            texture
+                .usage
+                .contains(TextureUsage::SAMPLED | TextureUsage::OUTPUT_ATTACHMENT),
+            "Renderer::generate_mipmaps: texture must be created with the \
+             `SAMPLED` and `OUTPUT_ATTACHMENT` usages"
+        );
+        if texture.mip_levels <= 1 {
+            return;
         }
-    }
 
-    pub fn texture(&self, w: u32, h: u32) -> Texture {
-        self.device.create_texture(w, h)
-    }
+        let set = Set(&[
+            Binding {
+                binding: BindingType::SampledTexture,
+                stage: ShaderStage::Fragment,
+            },
+            Binding {
+                binding: BindingType::Sampler,
+                stage: ShaderStage::Fragment,
+            },
+        ]);
+        let vs = self.device.create_shader(
+            "mipmap vertex shader",
+            include_bytes!("data/mipmap.vert.spv"),
+            ShaderStage::Vertex,
+        );
+        let fs = self.device.create_shader(
+            "mipmap fragment shader",
+            include_bytes!("data/mipmap.frag.spv"),
+            ShaderStage::Fragment,
+        );
+        // The full-screen triangle is generated in the vertex shader.
+        let pipeline = self
+            .device
+            .create_pipeline::<()>(
+                &[set],
+                &[],
+                Blending::constant(),
+                texture.format,
+                IndexFormat::Uint16,
+                &vs,
+                &fs,
+            )
+            .unwrap();
+        let sampler = self.device.create_sampler_with(
+            &SamplerDescriptor::default()
+                .filter(Filter::Linear, Filter::Linear)
+                .address_mode(AddressMode::ClampToEdge),
+        );
+        let mut encoder = self.device.create_command_encoder();
 
-    pub fn framebuffer(&self, w: u32, h: u32) -> Framebuffer {
-        self.device.create_framebuffer(w, h)
+        for layer in 0..texture.layers {
+            for level in 1..texture.mip_levels {
+                let src = texture.level_view(level - 1, layer);
+                let dst = texture.level_view(level, layer);
+                let binding = self
+                    .device
+                    .create_binding_group(&pipeline.layout.sets[0], &[&src, &sampler]);
+
+                let mut pass = Pass::begin(&mut encoder, &dst.view, PassOp::Load());
+                pass.wgpu.set_pipeline(&pipeline.wgpu);
+                pass.set_binding(&binding, &[]);
+                pass.wgpu.draw(0..3, 0..1);
+            }
+        }
+        self.device.submit(&[encoder.finish()]);
     }
 
-    pub fn vertex_buffer<T>(&self, verts: &[T]) -> VertexBuffer
-    where
-        T: 'static + Copy,
-    {
-        self.device.create_buffer(verts)
+    /// Process pending GPU callbacks without blocking, eg. to make progress
+    /// on `Renderer::read_pixels_async`.
+    pub fn poll(&self) {
+        self.device.device.poll(false);
     }
 
-    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
-    where
-        T: 'static + Copy,
-    {
-        self.device.create_uniform_buffer(buf)
-    }
+    /// Read the contents of a framebuffer into host memory, blocking until
+    /// the GPU is done. Framebuffers of any format are converted to 8-bit
+    /// RGBA: single-channel ones to grayscale, and half-float ones are
+    /// clamped to the `0..1` range.
+    pub fn capture(&mut self, fb: &Framebuffer) -> Image {
+        let (texture, rect) = (&fb.texture, Rect::origin(fb.width(), fb.height()));
 
-    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
-        self.device.create_binding_group(layout, binds)
+        let pixels = match texture.format() {
+            TextureFormat::Rgba8 | TextureFormat::Rgba8Srgb => {
+                self.read_texture_level(texture, 0, 0, rect)
+            }
+            TextureFormat::Bgra8 | TextureFormat::Bgra8Srgb => self
+                .read_texture_level::<Bgra8>(texture, 0, 0, rect)
+                .into_iter()
+                .map(Bgra8::into)
+                .collect(),
+            TextureFormat::R8 => self
+                .read_texture_level::<u8>(texture, 0, 0, rect)
+                .into_iter()
+                .map(|v| Rgba8::new(v, v, v, 0xff))
+                .collect(),
+            TextureFormat::Rgba16Float => self
+                .read_texture_level::<[u16; 4]>(texture, 0, 0, rect)
+                .into_iter()
+                .map(|[r, g, b, a]| Rgba8::new(unorm8(r), unorm8(g), unorm8(b), unorm8(a)))
+                .collect(),
+        };
+        Image::new(fb.width(), fb.height(), pixels)
     }
 
-    pub fn sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
-        self.device.create_sampler(min_filter, mag_filter)
+    /// Block until a pending read completes.
+    fn wait<T: Copy>(&self, future: ReadPixels<T>) -> Vec<T> {
+        self.device.device.poll(true);
+
+        let mut state = future.state.lock().unwrap();
+        state
+            .pixels
+            .take()
+            .expect("Renderer::wait: buffer wasn't mapped after polling")
     }
 
-    pub fn pipeline<T>(&self, w: u32, h: u32, blending: Blending) -> T
-    where
-        T: AbstractPipeline<'static>,
-    {
-        let desc = T::description();
-        let pip_layout = self.device.create_pipeline_layout(desc.pipeline_layout);
-        let vertex_layout = VertexLayout::from(desc.vertex_layout);
-        let vs =
-            self.device
-                .create_shader("vertex shader", desc.vertex_shader, ShaderStage::Vertex);
-        let fs = self.device.create_shader(
-            "fragment shader",
-            desc.fragment_shader,
-            ShaderStage::Fragment,
+    fn read_async<T: Pixel>(
+        &mut self,
+        texture: &Texture,
+        level: u32,
+        layer: u32,
+        rect: Rect<u32>,
+    ) -> ReadPixels<T> {
+        texture.check_format::<T>();
+        assert!(
+            texture.usage.contains(TextureUsage::COPY_SRC),
+            "Renderer::read: texture must be created with the `COPY_SRC` usage"
+        );
+        assert!(
+            level < texture.mip_levels && layer < texture.layers,
+            "Renderer::read: mip level {} or layer {} is out of bounds",
+            level,
+            layer
         );
 
-        T::setup(
-            self.device
-                .create_pipeline(pip_layout, vertex_layout, blending, &vs, &fs),
-            &self.device,
-            w,
-            h,
-        )
-    }
+        let rect = rect.normalized();
+        let (tw, th) = texture.level_size(level);
 
-    pub fn read<F>(&mut self, fb: &Framebuffer, f: F)
-    where
-        F: 'static + FnOnce(&[u8]),
-    {
-        let mut encoder = self.device.create_command_encoder();
+        assert!(
+            rect.x2 <= tw && rect.y2 <= th,
+            "Renderer::read: {:?} is out of bounds of the {}x{} texture",
+            rect,
+            tw,
+            th
+        );
+        let texel = std::mem::size_of::<T>() as u32;
+        let (w, h) = (rect.width(), rect.height());
+
+        if w == 0 || h == 0 {
+            return ReadPixels {
+                state: Arc::new(Mutex::new(ReadPixelsState {
+                    pixels: Some(Vec::new()),
+                    waker: None,
+                })),
+                _buffer: None,
+            };
+        }
+        // Rows copied into buffers must be aligned to 256 bytes.
+        let row_pitch = (texel * w + 255) / 256 * 256;
+        let bytesize = row_pitch as u64 * h as u64;
 
-        let bytesize = 4 * fb.size();
-        let dst = self.device.device.create_buffer(&wgpu::BufferDescriptor {
-            size: bytesize as u64,
+        let buffer = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+            size: bytesize,
             usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
         });
+        let mut encoder = self.device.create_command_encoder();
 
         encoder.copy_texture_to_buffer(
             wgpu::TextureCopyView {
-                texture: &fb.texture.wgpu,
-                mip_level: 0,
-                array_layer: 0,
+                texture: &texture.wgpu,
+                mip_level: level,
+                array_layer: layer,
                 origin: wgpu::Origin3d {
-                    x: 0.0,
-                    y: 0.0,
+                    x: rect.x1 as f32,
+                    y: (th - rect.y2) as f32,
                     z: 0.0,
                 },
             },
             wgpu::BufferCopyView {
-                buffer: &dst,
+                buffer: &buffer,
                 offset: 0,
-                // TODO: Must be a multiple of 256
-                row_pitch: 4 * fb.texture.w,
-                image_height: fb.texture.h,
+                row_pitch,
+                image_height: h,
+            },
+            wgpu::Extent3d {
+                width: w,
+                height: h,
+                depth: 1,
             },
-            fb.texture.extent,
         );
         self.device.submit(&[encoder.finish()]);
 
-        let mut buffer: Vec<u8> = Vec::with_capacity(bytesize);
+        let state = Arc::new(Mutex::new(ReadPixelsState {
+            pixels: None,
+            waker: None,
+        }));
+        let mapped = state.clone();
 
-        dst.map_read_async(
+        buffer.map_read_async(
             0,
-            bytesize as u64,
+            bytesize,
             move |result: wgpu::BufferMapAsyncResult<&[u8]>| match result {
                 Ok(ref mapping) => {
-                    buffer.extend_from_slice(mapping.data);
-                    if buffer.len() == bytesize {
-                        f(unsafe { std::mem::transmute(buffer.as_slice()) });
+                    let mut state = mapped.lock().unwrap();
+                    let mut pixels = Vec::with_capacity((w * h) as usize);
+
+                    // Strip the padding at the end of each row.
+                    for row in mapping.data.chunks(row_pitch as usize) {
+                        pixels.extend(
+                            row[..(texel * w) as usize]
+                                .chunks(texel as usize)
+                                // Safe because the texture format matches `T`.
+                                .map(|c| unsafe {
+                                    std::ptr::read_unaligned(c.as_ptr() as *const T)
+                                }),
+                        );
+                    }
+                    state.pixels = Some(pixels);
+
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
                     }
                 }
                 Err(ref err) => panic!("{:?}", err),
             },
         );
+
+        ReadPixels {
+            state,
+            _buffer: Some(buffer),
+        }
     }
 
     // MUTABLE API ////////////////////////////////////////////////////////////
@@ -1672,11 +3640,252 @@ impl Renderer {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// ReadPixels
+///////////////////////////////////////////////////////////////////////////////
+
+/// A pending texture read, returned by `Renderer::read_pixels_async` and
+/// `Renderer::read_texture_async`.
+pub struct ReadPixels<T> {
+    state: Arc<Mutex<ReadPixelsState<T>>>,
+    /// Kept alive until the read completes. Empty reads have no buffer.
+    _buffer: Option<wgpu::Buffer>,
+}
+
+struct ReadPixelsState<T> {
+    pixels: Option<Vec<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T> Future for ReadPixels<T> {
+    type Output = Vec<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.pixels.take() {
+            Some(pixels) => Poll::Ready(pixels),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Image
+///////////////////////////////////////////////////////////////////////////////
+
+/// An RGBA image in host memory, with rows ordered from top to bottom.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub w: u32,
+    pub h: u32,
+    pub pixels: Vec<Rgba8>,
+}
+
+impl Image {
+    pub fn new(w: u32, h: u32, pixels: Vec<Rgba8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (w * h) as usize,
+            "Image::new: buffer size doesn't match image size"
+        );
+        Self { w, h, pixels }
+    }
+
+    /// The image pixels as bytes, in RGBA order.
+    pub fn bytes(&self) -> &[u8] {
+        // Safe because `Rgba8` is `repr(C)` and made of four `u8`s.
+        unsafe {
+            std::slice::from_raw_parts(self.pixels.as_ptr() as *const u8, self.pixels.len() * 4)
+        }
+    }
+
+    /// Encode the image as PNG into the given writer.
+    #[cfg(feature = "image")]
+    pub fn write_png<W: std::io::Write>(&self, writer: W) -> Result<(), ImageError> {
+        image::png::PNGEncoder::new(writer)
+            .encode(self.bytes(), self.w, self.h, image::ColorType::RGBA(8))
+            .map_err(ImageError::Encode)
+    }
+
+    /// Save the image to a file. The format is determined by the file
+    /// extension, and can be any format supported by the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), ImageError> {
+        let path = path.as_ref();
+
+        image::save_buffer(
+            path,
+            self.bytes(),
+            self.w,
+            self.h,
+            image::ColorType::RGBA(8),
+        )
+        .map_err(|e| ImageError::Write(path.to_owned(), e))
+    }
+}
+
+/// Convert an IEEE 754 binary16 value to an 8-bit normalized value, clamping
+/// it to the `0..1` range. NaN converts to zero.
+fn unorm8(half: u16) -> u8 {
+    let (exp, mantissa) = ((half >> 10) & 0x1f, (half & 0x3ff) as f32 / 1024.);
+
+    let v = if half & 0x8000 != 0 {
+        0.
+    } else if exp == 0 {
+        mantissa * 2f32.powi(-14)
+    } else if exp == 0x1f {
+        if mantissa == 0. {
+            1.
+        } else {
+            0.
+        }
+    } else {
+        (1. + mantissa) * 2f32.powi(exp as i32 - 15)
+    };
+    (v.min(1.) * 255.).round() as u8
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Image loading
+///////////////////////////////////////////////////////////////////////////////
+
+/// Options for loading images into textures.
+#[cfg(feature = "image")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImageOptions {
+    /// Multiply the color channels by the alpha channel.
+    pub premultiply: bool,
+    /// Flip the image vertically, so that its first row is the bottom one.
+    pub flip_y: bool,
+    /// Create the texture with a full mip chain, generated on the GPU.
+    pub mipmaps: bool,
+}
+
+/// An error encountered while loading or saving an image.
+#[cfg(feature = "image")]
+#[derive(Debug)]
+pub enum ImageError {
+    /// The image file couldn't be read.
+    Io(std::path::PathBuf, std::io::Error),
+    /// The image file couldn't be written.
+    Write(std::path::PathBuf, std::io::Error),
+    /// The image data couldn't be decoded.
+    Decode(image::ImageError),
+    /// The image couldn't be encoded and written.
+    Encode(std::io::Error),
+}
+
+#[cfg(feature = "image")]
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "error reading {}: {}", path.display(), err),
+            Self::Write(path, err) => write!(f, "error writing {}: {}", path.display(), err),
+            Self::Decode(err) => write!(f, "error decoding image: {}", err),
+            Self::Encode(err) => write!(f, "error encoding image: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+impl std::error::Error for ImageError {}
+
+#[cfg(feature = "image")]
+impl From<image::ImageError> for ImageError {
+    fn from(err: image::ImageError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+#[cfg(feature = "image")]
+impl Renderer {
+    /// Load a PNG, JPEG, TGA or BMP file into a new texture. The format is
+    /// determined by the file extension, or by the file contents.
+    pub fn texture_from_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+        opts: ImageOptions,
+    ) -> Result<Texture, ImageError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| ImageError::Io(path.to_owned(), e))?;
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        let format = match ext.as_deref() {
+            Some("png") => image::ImageFormat::PNG,
+            Some("jpg") | Some("jpeg") => image::ImageFormat::JPEG,
+            Some("tga") => image::ImageFormat::TGA,
+            Some("bmp") => image::ImageFormat::BMP,
+            _ => Self::image_format(&bytes),
+        };
+        self.texture_from_image(image::load_from_memory_with_format(&bytes, format)?, opts)
+    }
+
+    /// Load an encoded PNG, JPEG, TGA or BMP image into a new texture. Data
+    /// which isn't recognized as any other format is decoded as TGA, since
+    /// TGA has no signature.
+    pub fn texture_from_bytes(
+        &mut self,
+        bytes: &[u8],
+        opts: ImageOptions,
+    ) -> Result<Texture, ImageError> {
+        let img = image::load_from_memory_with_format(bytes, Self::image_format(bytes))?;
+        self.texture_from_image(img, opts)
+    }
+
+    fn image_format(bytes: &[u8]) -> image::ImageFormat {
+        image::guess_format(bytes).unwrap_or(image::ImageFormat::TGA)
+    }
+
+    fn texture_from_image(
+        &mut self,
+        img: image::DynamicImage,
+        opts: ImageOptions,
+    ) -> Result<Texture, ImageError> {
+        let img = if opts.flip_y { img.flipv() } else { img };
+        // Expands grayscale and RGB images to RGBA.
+        let img = img.to_rgba();
+        let (w, h) = img.dimensions();
+
+        let texels: Vec<Rgba8> = img
+            .pixels()
+            .map(|p| {
+                let (r, g, b, a) = (p[0], p[1], p[2], p[3]);
+
+                if opts.premultiply {
+                    let mul = |c: u8| ((c as u16 * a as u16 + 127) / 255) as u8;
+                    Rgba8::new(mul(r), mul(g), mul(b), a)
+                } else {
+                    Rgba8::new(r, g, b, a)
+                }
+            })
+            .collect();
+
+        let texture = if opts.mipmaps {
+            self.texture_with(w, h, TextureOptions::mipmapped(w, h))
+        } else {
+            self.texture(w, h)
+        };
+        self.prepare(&[Op::Fill(&texture, texels.as_slice())]);
+
+        if opts.mipmaps {
+            self.generate_mipmaps(&texture);
+        }
+        Ok(texture)
+    }
+}
+
 pub enum Op<'a, T> {
-    Clear(&'a dyn Canvas<Color = T>, T),
-    Fill(&'a dyn Canvas<Color = T>, &'a [T]),
-    Transfer(&'a dyn Canvas<Color = T>, &'a [T], u32, u32, Rect<i32>),
-    Blit(&'a dyn Canvas<Color = T>, Rect<f32>, Rect<f32>),
+    Clear(&'a dyn Canvas<T>, T),
+    Fill(&'a dyn Canvas<T>, &'a [T]),
+    Transfer(&'a dyn Canvas<T>, &'a [T], u32, u32, Rect<i32>),
+    Blit(&'a dyn Canvas<T>, Rect<f32>, Rect<f32>),
 }
 
 impl<'a, T> Op<'a, T>
@@ -1705,13 +3914,25 @@ where
 /// Device
 ///////////////////////////////////////////////////////////////////////////////
 
+/// Optional device features. None are enabled by default.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Extensions {
+    /// Use anisotropic filtering in all samplers of the device.
+    pub anisotropic_filtering: bool,
+}
+
 pub struct Device {
     device: wgpu::Device,
     surface: wgpu::Surface,
+    extensions: Extensions,
 }
 
 impl Device {
     pub fn new(window: RawWindowHandle) -> Self {
+        Self::with_extensions(window, Extensions::default())
+    }
+
+    pub fn with_extensions(window: RawWindowHandle, extensions: Extensions) -> Self {
         let instance = wgpu::Instance::new();
         let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::LowPower,
@@ -1721,14 +3942,20 @@ impl Device {
         Self {
             device: adapter.request_device(&wgpu::DeviceDescriptor {
                 extensions: wgpu::Extensions {
-                    anisotropic_filtering: false,
+                    anisotropic_filtering: extensions.anisotropic_filtering,
                 },
                 limits: wgpu::Limits::default(),
             }),
             surface,
+            extensions,
         }
     }
 
+    /// The extensions enabled on the device.
+    pub fn extensions(&self) -> Extensions {
+        self.extensions
+    }
+
     pub fn device(&self) -> &wgpu::Device {
         &self.device
     }
@@ -1743,7 +3970,17 @@ impl Device {
     }
 
     pub fn create_swap_chain(&self, w: u32, h: u32, mode: PresentMode) -> wgpu::SwapChain {
-        let desc = SwapChain::descriptor(w, h, mode);
+        self.create_swap_chain_with(w, h, mode, TextureFormat::Bgra8)
+    }
+
+    pub fn create_swap_chain_with(
+        &self,
+        w: u32,
+        h: u32,
+        mode: PresentMode,
+        format: TextureFormat,
+    ) -> wgpu::SwapChain {
+        let desc = SwapChain::descriptor(w, h, mode, format);
         self.device.create_swap_chain(&self.surface, &desc)
     }
 
@@ -1755,21 +3992,86 @@ impl Device {
         PipelineLayout { sets }
     }
 
-    pub fn create_shader(&self, _name: &str, source: &[u8], _stage: ShaderStage) -> Shader {
+    pub fn create_shader(&self, name: &str, source: &[u8], stage: ShaderStage) -> Shader {
         let buf = std::io::Cursor::new(source);
         let spv = wgpu::read_spirv(buf).unwrap();
 
+        let interface = match reflect::reflect(source) {
+            Ok(module) => Some(module),
+            Err(err) => {
+                log::warn!(
+                    "shader `{}` couldn't be reflected, pipelines using it won't be validated: {}",
+                    name,
+                    err
+                );
+                None
+            }
+        };
+
         Shader {
             module: self.device.create_shader_module(spv.as_slice()),
+            stage,
+            interface,
         }
     }
 
+    /// Compile a shader from source code. See `compile_shader`.
+    #[cfg(feature = "shader-compiler")]
+    pub fn compile_shader(
+        &self,
+        name: &str,
+        source: &str,
+        lang: ShaderLanguage,
+        stage: ShaderStage,
+    ) -> Result<Shader, ShaderError> {
+        let spirv = compile_shader(source, lang, stage)?;
+
+        Ok(self.create_shader(name, &spirv, stage))
+    }
+
+    /// Create a compute pipeline, checking that the pipeline layout matches
+    /// the bindings of the compute shader.
+    pub fn create_compute_pipeline(
+        &self,
+        sets: &[Set],
+        cs: &Shader,
+    ) -> Result<ComputePipeline, PipelineError> {
+        validate_pipeline(&[], sets, &[cs])?;
+
+        let pipeline_layout = self.create_pipeline_layout(sets);
+        let layouts: Vec<&wgpu::BindGroupLayout> =
+            pipeline_layout.sets.iter().map(|s| &s.wgpu).collect();
+        let layout = &self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                bind_group_layouts: layouts.as_slice(),
+            });
+        let wgpu = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                layout,
+                compute_stage: wgpu::ProgrammableStageDescriptor {
+                    module: &cs.module,
+                    entry_point: "main",
+                },
+            });
+
+        Ok(ComputePipeline {
+            wgpu,
+            layout: pipeline_layout,
+        })
+    }
+
     pub fn create_encoder(&self) -> wgpu::CommandEncoder {
         self.device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { todo: 0 })
     }
 
     pub fn create_texture(&self, w: u32, h: u32) -> Texture {
+        self.create_texture_with(w, h, TextureOptions::default())
+    }
+
+    pub fn create_texture_with(&self, w: u32, h: u32, opts: TextureOptions) -> Texture {
         let texture_extent = wgpu::Extent3d {
             width: w,
             height: h,
@@ -1777,12 +4079,12 @@ impl Device {
         };
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             size: texture_extent,
-            array_layer_count: 1,
-            mip_level_count: 1,
+            array_layer_count: opts.layers,
+            mip_level_count: opts.mip_levels,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            format: opts.format.to_wgpu(),
+            usage: opts.usage.to_wgpu(),
         });
         let texture_view = texture.create_default_view();
 
@@ -1790,28 +4092,37 @@ impl Device {
             wgpu: texture,
             view: texture_view,
             extent: texture_extent,
+            format: opts.format,
+            usage: opts.usage,
+            mip_levels: opts.mip_levels,
+            layers: opts.layers,
             w,
             h,
         }
     }
 
     pub fn create_framebuffer(&self, w: u32, h: u32) -> Framebuffer {
+        self.create_framebuffer_with(w, h, TextureFormat::Bgra8)
+    }
+
+    pub fn create_framebuffer_with(&self, w: u32, h: u32, format: TextureFormat) -> Framebuffer {
         let extent = wgpu::Extent3d {
             width: w,
             height: h,
             depth: 1,
         };
+        let usage = TextureUsage::SAMPLED
+            | TextureUsage::COPY_DST
+            | TextureUsage::COPY_SRC
+            | TextureUsage::OUTPUT_ATTACHMENT;
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             size: extent,
             array_layer_count: 1,
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Bgra8Unorm,
-            usage: wgpu::TextureUsage::SAMPLED
-                | wgpu::TextureUsage::COPY_DST
-                | wgpu::TextureUsage::COPY_SRC
-                | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            format: format.to_wgpu(),
+            usage: usage.to_wgpu(),
         });
         let view = texture.create_default_view();
 
@@ -1820,6 +4131,10 @@ impl Device {
                 wgpu: texture,
                 view,
                 extent,
+                format,
+                usage,
+                mip_levels: 1,
+                layers: 1,
                 w,
                 h,
             },
@@ -1852,16 +4167,20 @@ impl Device {
         )
     }
 
-    pub fn create_buffer<T>(&self, vertices: &[T]) -> VertexBuffer
-    where
-        T: 'static + Copy,
-    {
+    pub fn create_buffer<V: Vertex>(&self, vertices: &[V]) -> VertexBuffer<V> {
+        debug_assert_eq!(
+            V::FORMATS.iter().map(|f| f.bytesize()).sum::<usize>(),
+            std::mem::size_of::<V>(),
+            "Device::create_buffer: vertex size doesn't match its formats, \
+             it may have padding"
+        );
         VertexBuffer {
             wgpu: self
                 .device
                 .create_buffer_mapped(vertices.len(), wgpu::BufferUsage::VERTEX)
                 .fill_from_slice(vertices),
             size: vertices.len() as u32,
+            vertex: PhantomData,
         }
     }
 
@@ -1884,26 +4203,69 @@ impl Device {
         }
     }
 
-    pub fn create_index(&self, indices: &[u16]) -> IndexBuffer {
+    /// Create a storage buffer initialized with the given elements. The
+    /// buffer can also be used as a vertex buffer, and copied to and from.
+    /// Storage buffers can't be empty, as empty buffers can't be bound.
+    pub fn create_storage_buffer<T>(&self, buf: &[T]) -> StorageBuffer
+    where
+        T: 'static + Copy,
+    {
+        assert!(
+            !buf.is_empty() && std::mem::size_of::<T>() > 0,
+            "Device::create_storage_buffer: storage buffers can't be empty"
+        );
+        StorageBuffer {
+            size: std::mem::size_of::<T>(),
+            count: buf.len(),
+            wgpu: self
+                .device
+                .create_buffer_mapped::<T>(
+                    buf.len(),
+                    wgpu::BufferUsage::STORAGE
+                        | wgpu::BufferUsage::VERTEX
+                        | wgpu::BufferUsage::COPY_DST
+                        | wgpu::BufferUsage::COPY_SRC,
+                )
+                .fill_from_slice(buf),
+        }
+    }
+
+    /// Create an index buffer of `u16` or `u32` indices.
+    pub fn create_index<I: IndexType>(&self, indices: &[I]) -> IndexBuffer {
         let index_buf = self
             .device
             .create_buffer_mapped(indices.len(), wgpu::BufferUsage::INDEX)
             .fill_from_slice(indices);
-        IndexBuffer { wgpu: index_buf }
+        IndexBuffer {
+            wgpu: index_buf,
+            size: indices.len() as u32,
+            format: I::FORMAT,
+        }
     }
 
     pub fn create_sampler(&self, min_filter: Filter, mag_filter: Filter) -> Sampler {
+        self.create_sampler_with(&SamplerDescriptor {
+            min_filter,
+            mag_filter,
+            ..SamplerDescriptor::default()
+        })
+    }
+
+    pub fn create_sampler_with(&self, desc: &SamplerDescriptor) -> Sampler {
         Sampler {
             wgpu: self.device.create_sampler(&wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::Repeat,
-                address_mode_v: wgpu::AddressMode::Repeat,
-                address_mode_w: wgpu::AddressMode::Repeat,
-                mag_filter: mag_filter.to_wgpu(),
-                min_filter: min_filter.to_wgpu(),
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                lod_min_clamp: -100.0,
-                lod_max_clamp: 100.0,
-                compare_function: wgpu::CompareFunction::Always,
+                address_mode_u: desc.address_mode_u.to_wgpu(),
+                address_mode_v: desc.address_mode_v.to_wgpu(),
+                address_mode_w: desc.address_mode_w.to_wgpu(),
+                mag_filter: desc.mag_filter.to_wgpu(),
+                min_filter: desc.min_filter.to_wgpu(),
+                mipmap_filter: desc.mipmap_filter.to_wgpu(),
+                lod_min_clamp: desc.lod.start,
+                lod_max_clamp: desc.lod.end,
+                // Samplers without a comparison function always pass.
+                compare_function: desc
+                    .compare
+                    .map_or(wgpu::CompareFunction::Always, CompareFunction::to_wgpu),
             }),
         }
     }
@@ -1959,15 +4321,42 @@ impl Device {
 
     // PRIVATE API ////////////////////////////////////////////////////////////
 
-    fn create_pipeline(
+    #[allow(clippy::too_many_arguments)]
+    fn create_pipeline<V: Vertex>(
         &self,
-        pipeline_layout: PipelineLayout,
-        vertex_layout: VertexLayout,
+        sets: &[Set],
+        vertex_buffers: &[VertexBufferLayout],
         blending: Blending,
+        format: TextureFormat,
+        index_format: IndexFormat,
         vs: &Shader,
         fs: &Shader,
-    ) -> Pipeline {
-        let vertex_attrs = vertex_layout.to_wgpu();
+    ) -> Result<Pipeline<V>, PipelineError> {
+        let vertex_type_matches = match vertex_buffers.first() {
+            Some(layout) => *layout == V::LAYOUT,
+            None => V::FORMATS.is_empty(),
+        };
+        if !vertex_type_matches {
+            return Err(PipelineError::VertexTypeMismatch {
+                vertex_type: std::any::type_name::<V>().to_owned(),
+            });
+        }
+        let vertex_formats: Vec<VertexFormat> = vertex_buffers
+            .iter()
+            .flat_map(|b| b.formats.iter().cloned())
+            .collect();
+        validate_pipeline(&vertex_formats, sets, &[vs, fs])?;
+
+        let pipeline_layout = self.create_pipeline_layout(sets);
+        let mut vertex_layout = Vec::new();
+        let mut location = 0;
+
+        for b in vertex_buffers {
+            vertex_layout.push(VertexLayout::new(*b, location));
+            location += b.formats.len() as u32;
+        }
+        let vertex_attrs: Vec<wgpu::VertexBufferDescriptor> =
+            vertex_layout.iter().map(|l| l.to_wgpu()).collect();
 
         let mut sets = Vec::new();
         for s in pipeline_layout.sets.iter() {
@@ -2002,8 +4391,7 @@ impl Device {
                 }),
                 primitive_topology: wgpu::PrimitiveTopology::TriangleList,
                 color_states: &[wgpu::ColorStateDescriptor {
-                    // TODO: Try Bgra8UnormSrgb
-                    format: wgpu::TextureFormat::Bgra8Unorm,
+                    format: format.to_wgpu(),
                     color_blend: wgpu::BlendDescriptor {
                         src_factor,
                         dst_factor,
@@ -2017,17 +4405,19 @@ impl Device {
                     write_mask: wgpu::ColorWrite::ALL,
                 }],
                 depth_stencil_state: None,
-                index_format: wgpu::IndexFormat::Uint16,
-                vertex_buffers: &[vertex_attrs],
+                index_format: index_format.to_wgpu(),
+                vertex_buffers: &vertex_attrs,
                 sample_count: 1,
                 sample_mask: !0,
                 alpha_to_coverage_enabled: false,
             });
 
-        Pipeline {
+        Ok(Pipeline {
             layout: pipeline_layout,
             vertex_layout,
             wgpu,
-        }
+            index_format,
+            vertex: PhantomData,
+        })
     }
 }
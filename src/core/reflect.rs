@@ -0,0 +1,298 @@
+//! Minimal SPIR-V reflection, used to check pipeline descriptions against
+//! the shaders they use.
+use std::collections::HashMap;
+use std::fmt;
+
+const MAGIC: u32 = 0x0723_0203;
+const HEADER_LEN: usize = 5;
+
+// Opcodes.
+const OP_NAME: u32 = 5;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+
+// Decorations.
+const BLOCK: u32 = 2;
+const BUFFER_BLOCK: u32 = 3;
+const BUILT_IN: u32 = 11;
+const LOCATION: u32 = 30;
+const BINDING: u32 = 33;
+const DESCRIPTOR_SET: u32 = 34;
+
+// Storage classes.
+const UNIFORM_CONSTANT: u32 = 0;
+const INPUT: u32 = 1;
+const UNIFORM: u32 = 2;
+const STORAGE_BUFFER: u32 = 12;
+
+/// The scalar type of a shader input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scalar {
+    Float,
+    Sint,
+    Uint,
+}
+
+/// A shader input variable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+    pub name: String,
+    pub location: u32,
+    pub scalar: Scalar,
+    pub components: u32,
+}
+
+impl Input {
+    /// The GLSL name of the input's type, eg. `vec4`.
+    pub fn type_name(&self) -> String {
+        let prefix = match self.scalar {
+            Scalar::Float => "",
+            Scalar::Sint => "i",
+            Scalar::Uint => "u",
+        };
+        match (self.scalar, self.components) {
+            (Scalar::Float, 1) => String::from("float"),
+            (Scalar::Sint, 1) => String::from("int"),
+            (Scalar::Uint, 1) => String::from("uint"),
+            (_, n) => format!("{}vec{}", prefix, n),
+        }
+    }
+}
+
+/// The type of a shader resource.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    UniformBuffer,
+    StorageBuffer,
+    Sampler,
+    SampledTexture,
+    StorageTexture,
+    /// A combined image and sampler, which isn't supported.
+    SampledImage,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UniformBuffer => write!(f, "uniform buffer"),
+            Self::StorageBuffer => write!(f, "storage buffer"),
+            Self::Sampler => write!(f, "sampler"),
+            Self::SampledTexture => write!(f, "sampled texture"),
+            Self::StorageTexture => write!(f, "storage texture"),
+            Self::SampledImage => write!(f, "combined texture and sampler"),
+        }
+    }
+}
+
+/// A shader resource variable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub name: String,
+    pub set: u32,
+    pub binding: u32,
+    pub kind: Kind,
+}
+
+/// The interface of a shader module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Module {
+    pub inputs: Vec<Input>,
+    pub resources: Vec<Resource>,
+}
+
+#[derive(Clone, Debug)]
+enum Type {
+    Scalar(Scalar),
+    Vector(u32, u32),
+    Image { sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array(u32),
+    Struct,
+    Pointer(u32),
+    Other,
+}
+
+/// Reflect the interface of a SPIR-V module, given as little-endian bytes.
+pub fn reflect(bytes: &[u8]) -> Result<Module, String> {
+    let chunks = bytes.chunks_exact(4);
+    if !chunks.remainder().is_empty() {
+        return Err(String::from("length is not a multiple of four"));
+    }
+    let words: Vec<u32> = chunks
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+
+    if words.len() < HEADER_LEN || words[0] != MAGIC {
+        return Err(String::from("invalid header"));
+    }
+
+    let mut names: HashMap<u32, String> = HashMap::new();
+    let mut decorations: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    let mut types: HashMap<u32, Type> = HashMap::new();
+    let mut variables: Vec<(u32, u32, u32)> = Vec::new();
+
+    let mut i = HEADER_LEN;
+    while i < words.len() {
+        let (len, op) = ((words[i] >> 16) as usize, words[i] & 0xffff);
+        if len == 0 || i + len > words.len() {
+            return Err(format!("invalid instruction at word {}", i));
+        }
+        let args = &words[i + 1..i + len];
+        let arg = |n: usize| {
+            args.get(n)
+                .copied()
+                .ok_or_else(|| format!("truncated instruction at word {}", i))
+        };
+
+        match op {
+            OP_NAME => {
+                names.insert(arg(0)?, string(&args[1..]));
+            }
+            OP_DECORATE => {
+                let value = args.get(2).copied().unwrap_or(0);
+                decorations
+                    .entry(arg(0)?)
+                    .or_default()
+                    .push((arg(1)?, value));
+            }
+            OP_TYPE_INT => {
+                let scalar = if arg(2)? == 0 {
+                    Scalar::Uint
+                } else {
+                    Scalar::Sint
+                };
+                types.insert(arg(0)?, Type::Scalar(scalar));
+            }
+            OP_TYPE_FLOAT => {
+                types.insert(arg(0)?, Type::Scalar(Scalar::Float));
+            }
+            OP_TYPE_VECTOR => {
+                types.insert(arg(0)?, Type::Vector(arg(1)?, arg(2)?));
+            }
+            OP_TYPE_IMAGE => {
+                types.insert(arg(0)?, Type::Image { sampled: arg(6)? });
+            }
+            OP_TYPE_SAMPLER => {
+                types.insert(arg(0)?, Type::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                types.insert(arg(0)?, Type::SampledImage);
+            }
+            OP_TYPE_ARRAY | OP_TYPE_RUNTIME_ARRAY => {
+                types.insert(arg(0)?, Type::Array(arg(1)?));
+            }
+            OP_TYPE_STRUCT => {
+                types.insert(arg(0)?, Type::Struct);
+            }
+            OP_TYPE_POINTER => {
+                types.insert(arg(0)?, Type::Pointer(arg(2)?));
+            }
+            OP_VARIABLE => {
+                variables.push((arg(1)?, arg(0)?, arg(2)?));
+            }
+            _ if is_type(op) => {
+                types.insert(arg(0)?, Type::Other);
+            }
+            _ => {}
+        }
+        i += len;
+    }
+
+    let decoration = |id: u32, d: u32| {
+        decorations
+            .get(&id)
+            .and_then(|ds| ds.iter().find(|(k, _)| *k == d))
+            .map(|(_, v)| *v)
+    };
+    let name = |id: u32| names.get(&id).cloned().unwrap_or_default();
+
+    let mut module = Module::default();
+
+    for (id, ty, class) in variables {
+        // Look through the pointer and any arrays.
+        let mut ty = match types.get(&ty) {
+            Some(Type::Pointer(t)) => *t,
+            _ => continue,
+        };
+        while let Some(Type::Array(t)) = types.get(&ty) {
+            ty = *t;
+        }
+
+        match class {
+            INPUT => {
+                let location = match decoration(id, LOCATION) {
+                    Some(l) if decoration(id, BUILT_IN).is_none() => l,
+                    _ => continue,
+                };
+                let (scalar, components) = match types.get(&ty) {
+                    Some(Type::Scalar(s)) => (*s, 1),
+                    Some(Type::Vector(t, n)) => match types.get(t) {
+                        Some(Type::Scalar(s)) => (*s, *n),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                module.inputs.push(Input {
+                    name: name(id),
+                    location,
+                    scalar,
+                    components,
+                });
+            }
+            UNIFORM_CONSTANT | UNIFORM | STORAGE_BUFFER => {
+                let (set, binding) = match (decoration(id, DESCRIPTOR_SET), decoration(id, BINDING))
+                {
+                    (Some(set), Some(binding)) => (set, binding),
+                    _ => continue,
+                };
+                let kind = match (class, types.get(&ty)) {
+                    (STORAGE_BUFFER, _) => Kind::StorageBuffer,
+                    (UNIFORM, _) if decoration(ty, BUFFER_BLOCK).is_some() => Kind::StorageBuffer,
+                    (UNIFORM, _) if decoration(ty, BLOCK).is_some() => Kind::UniformBuffer,
+                    (_, Some(Type::Sampler)) => Kind::Sampler,
+                    (_, Some(Type::SampledImage)) => Kind::SampledImage,
+                    (_, Some(Type::Image { sampled: 2 })) => Kind::StorageTexture,
+                    (_, Some(Type::Image { .. })) => Kind::SampledTexture,
+                    _ => continue,
+                };
+                module.resources.push(Resource {
+                    name: name(id),
+                    set,
+                    binding,
+                    kind,
+                });
+            }
+            _ => {}
+        }
+    }
+    module.inputs.sort_by_key(|i| i.location);
+
+    Ok(module)
+}
+
+/// Whether an opcode declares a type.
+fn is_type(op: u32) -> bool {
+    (19..=39).contains(&op)
+}
+
+/// Decode a nul-terminated literal string.
+fn string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|w| w.to_le_bytes().to_vec())
+        .take_while(|b| *b != 0)
+        .collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Blur {
+	vec2 step;
+} blur;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+// 9-tap Gaussian kernel, sampled in 5 fetches by using linear filtering
+// to blend adjacent texels.
+void main() {
+	vec2 o1 = blur.step * 1.3846153846;
+	vec2 o2 = blur.step * 3.2307692308;
+
+	fragColor = texture(sampler2D(tex, sam), f_uv) * 0.2270270270
+		+ texture(sampler2D(tex, sam), f_uv + o1) * 0.3162162162
+		+ texture(sampler2D(tex, sam), f_uv - o1) * 0.3162162162
+		+ texture(sampler2D(tex, sam), f_uv + o2) * 0.0702702703
+		+ texture(sampler2D(tex, sam), f_uv - o2) * 0.0702702703;
+}
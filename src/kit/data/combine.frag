@@ -0,0 +1,21 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Combine {
+	float intensity;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(set = 2, binding = 0) uniform texture2D scene;
+layout(set = 2, binding = 1) uniform sampler   scene_sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec4 bloom = texture(sampler2D(tex, sam), f_uv);
+	vec4 base = texture(sampler2D(scene, scene_sam), f_uv);
+
+	fragColor = vec4(base.rgb + bloom.rgb * u.intensity, base.a);
+}
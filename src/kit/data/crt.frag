@@ -0,0 +1,29 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Crt {
+	vec2  resolution;
+	float scanlines;
+	float curvature;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	// Bulge the image outwards from the center.
+	vec2 p = f_uv * 2.0 - 1.0;
+	p += p * (p.yx * p.yx) * u.curvature;
+	vec2 uv = p * 0.5 + 0.5;
+
+	if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+		fragColor = vec4(0.0, 0.0, 0.0, 1.0);
+		return;
+	}
+	vec4 texel = texture(sampler2D(tex, sam), uv);
+	float line = 0.5 + 0.5 * sin(uv.y * u.resolution.y * 3.1415926536);
+
+	fragColor = vec4(texel.rgb * mix(1.0, line, u.scanlines), texel.a);
+}
@@ -0,0 +1,33 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Lut {
+	float size;
+	float intensity;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+// A `size * size` by `size` strip of `size` slices, one per blue value.
+layout(set = 2, binding = 0) uniform texture2D lut;
+layout(set = 2, binding = 1) uniform sampler   lut_sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec4 texel = texture(sampler2D(tex, sam), f_uv);
+	vec3 c = clamp(texel.rgb, 0.0, 1.0);
+	float n = u.size;
+
+	float b = c.b * (n - 1.0);
+	float b0 = floor(b);
+	float b1 = min(b0 + 1.0, n - 1.0);
+	vec2 uv = vec2((c.r * (n - 1.0) + 0.5) / (n * n), (c.g * (n - 1.0) + 0.5) / n);
+
+	vec3 g0 = texture(sampler2D(lut, lut_sam), uv + vec2(b0 / n, 0.0)).rgb;
+	vec3 g1 = texture(sampler2D(lut, lut_sam), uv + vec2(b1 / n, 0.0)).rgb;
+	vec3 graded = mix(g0, g1, b - b0);
+
+	fragColor = vec4(mix(texel.rgb, graded, u.intensity), texel.a);
+}
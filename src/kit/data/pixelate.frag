@@ -0,0 +1,19 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Pixelate {
+	vec2  resolution;
+	float size;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec2 cell = u.size / u.resolution;
+	vec2 uv = (floor(f_uv / cell) + 0.5) * cell;
+
+	fragColor = texture(sampler2D(tex, sam), uv);
+}
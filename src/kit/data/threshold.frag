@@ -0,0 +1,20 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Threshold {
+	float threshold;
+	float knee;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec4 texel = texture(sampler2D(tex, sam), f_uv);
+	float luma = dot(texel.rgb, vec3(0.2126, 0.7152, 0.0722));
+	float weight = smoothstep(u.threshold - u.knee, u.threshold + u.knee, luma);
+
+	fragColor = vec4(texel.rgb * weight, texel.a);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Vignette {
+	vec4  color;
+	float radius;
+	float softness;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D tex;
+layout(set = 1, binding = 1) uniform sampler   sam;
+
+layout(location = 0) in  vec2 f_uv;
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+	vec4 texel = texture(sampler2D(tex, sam), f_uv);
+	// Distance from the center, normalized so that the corners are at 1.
+	float d = distance(f_uv, vec2(0.5)) * 1.4142135624;
+	float k = smoothstep(u.radius - u.softness, u.radius, d) * u.color.a;
+
+	fragColor = vec4(mix(texel.rgb, u.color.rgb, k), texel.a);
+}
@@ -2,8 +2,19 @@
 pub use crate::core;
 pub use crate::core::{Bgra8, Rgba, Rgba8};
 
+pub mod pingpong;
+pub mod postfx;
+#[cfg(feature = "shader-compiler")]
+pub mod reload;
 pub mod shape2d;
+#[cfg(feature = "sheet")]
+pub mod sheet;
 pub mod sprite2d;
+pub mod text;
+pub mod tilemap;
+pub mod tween;
+#[cfg(feature = "ttf")]
+pub mod ttf;
 
 use crate::math::{Matrix4, Ortho};
 
@@ -38,21 +49,74 @@ impl Default for Repeat {
 // Animation
 ///////////////////////////////////////////////////////////////////////////
 
+/// How many times an animation is played through before it finishes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Playback {
+    /// Play through once.
+    Once,
+    /// Play through the given number of times.
+    Times(u32),
+    /// Play forever.
+    Loop,
+}
+
+/// The order in which the frames of an animation are played.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the first frame to the last.
+    Forward,
+    /// From the last frame to the first.
+    Reverse,
+    /// From the first frame to the last and back. A finished ping-pong
+    /// animation rests on its first frame.
+    PingPong,
+}
+
+/// An event triggered while stepping an animation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// A frame was entered. If `skipped` is set, the frame was passed over
+    /// during the step, and isn't the current frame.
+    Frame { index: usize, skipped: bool },
+    /// A frame with a marker was entered.
+    Marker {
+        name: String,
+        index: usize,
+        skipped: bool,
+    },
+    /// A pass through the animation was completed, and a new one started.
+    Looped,
+    /// The animation finished.
+    Finished,
+}
+
+/// Playback state of an animation. Holds the current frame index and the
+/// playback time elapsed since the start of the animation.
 #[derive(Clone, Debug)]
 pub enum AnimationState {
     Playing(u64, time::Duration),
     Paused(u64, time::Duration),
+    Finished(u64, time::Duration),
     Stopped,
 }
 
 #[derive(Clone, Debug)]
 pub struct Animation<T> {
     pub state: AnimationState,
+    /// Duration of frames which don't have a duration of their own.
     pub delay: time::Duration,
     pub frames: Vec<T>,
+    pub playback: Playback,
+    pub direction: Direction,
+    /// Playback speed multiplier. Negative values are treated as zero.
+    pub speed: f32,
+
+    durations: Vec<Option<time::Duration>>,
+    markers: Vec<(usize, String)>,
 }
 
 impl<T> Animation<T> {
+    /// Create a looping animation, where every frame lasts `delay`.
     pub fn new(frames: &[T], delay: time::Duration) -> Self
     where
         T: Clone,
@@ -61,28 +125,248 @@ impl<T> Animation<T> {
             state: AnimationState::Playing(0, time::Duration::from_secs(0)),
             delay,
             frames: frames.to_vec(),
+            playback: Playback::Loop,
+            direction: Direction::Forward,
+            speed: 1.0,
+            durations: vec![None; frames.len()],
+            markers: Vec::new(),
         }
     }
 
-    pub fn step(&mut self, delta: time::Duration) {
+    /// Create a looping animation, where every frame has its own duration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::Animation;
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::timed(&[('a', ms(100)), ('b', ms(50)), ('c', ms(50))]);
+    ///
+    /// anim.step(ms(99));
+    /// assert_eq!(anim.val(), 'a');
+    /// anim.step(ms(1));
+    /// assert_eq!(anim.val(), 'b');
+    /// anim.step(ms(50));
+    /// assert_eq!(anim.val(), 'c');
+    /// anim.step(ms(50));
+    /// assert_eq!(anim.val(), 'a');
+    /// ```
+    pub fn timed(frames: &[(T, time::Duration)]) -> Self
+    where
+        T: Clone,
+    {
+        let (frames, durations): (Vec<T>, Vec<Option<time::Duration>>) = frames
+            .iter()
+            .map(|(frame, duration)| (frame.clone(), Some(*duration)))
+            .unzip();
+
+        Self {
+            durations,
+            ..Self::new(&frames, time::Duration::from_secs(0))
+        }
+    }
+
+    /// Set how many times the animation is played through.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::{Animation, Playback};
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&[1, 2], ms(10)).playback(Playback::Times(2));
+    ///
+    /// anim.step(ms(35));
+    /// assert_eq!(anim.val(), 2);
+    /// assert!(!anim.is_finished());
+    ///
+    /// anim.step(ms(5));
+    /// assert!(anim.is_finished());
+    /// assert_eq!(anim.val(), 2);
+    /// assert_eq!(anim.elapsed(), ms(40));
+    /// ```
+    pub fn playback(mut self, playback: Playback) -> Self {
+        self.playback = playback;
+        self
+    }
+
+    /// Set the order in which frames are played.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::{Animation, Direction, Playback};
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&[1, 2, 3], ms(10))
+    ///     .direction(Direction::PingPong)
+    ///     .playback(Playback::Once);
+    /// let mut frames = vec![anim.val()];
+    ///
+    /// for _ in 0..4 {
+    ///     anim.step(ms(10));
+    ///     frames.push(anim.val());
+    /// }
+    /// assert_eq!(frames, vec![1, 2, 3, 2, 1]);
+    /// assert!(anim.is_finished());
+    ///
+    /// let mut anim = Animation::new(&[1, 2, 3], ms(10)).direction(Direction::Reverse);
+    /// assert_eq!(anim.val(), 3);
+    /// anim.step(ms(10));
+    /// assert_eq!(anim.val(), 2);
+    /// ```
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self.update(self.elapsed());
+        self
+    }
+
+    /// Set the playback speed multiplier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::Animation;
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&[1, 2, 3], ms(10)).speed(2.0);
+    ///
+    /// anim.step(ms(10));
+    /// assert_eq!(anim.val(), 3);
+    /// assert_eq!(anim.elapsed(), ms(20));
+    /// ```
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Advance the animation by `delta`, scaled by the playback speed, and
+    /// return the events triggered, in order. Every frame entered during the
+    /// step is reported, including frames which were skipped over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::{Animation, AnimationEvent, Playback};
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&["idle", "swing", "hit", "recover"], ms(10))
+    ///     .playback(Playback::Once);
+    /// anim.mark(2, "hit");
+    ///
+    /// assert_eq!(anim.step(ms(5)), vec![]);
+    /// assert_eq!(
+    ///     anim.step(ms(20)),
+    ///     vec![
+    ///         AnimationEvent::Frame { index: 1, skipped: true },
+    ///         AnimationEvent::Frame { index: 2, skipped: false },
+    ///         AnimationEvent::Marker { name: "hit".to_owned(), index: 2, skipped: false },
+    ///     ]
+    /// );
+    /// assert_eq!(
+    ///     anim.step(ms(100)),
+    ///     vec![
+    ///         AnimationEvent::Frame { index: 3, skipped: false },
+    ///         AnimationEvent::Finished,
+    ///     ]
+    /// );
+    /// assert_eq!(anim.step(ms(10)), vec![]);
+    /// ```
+    pub fn step(&mut self, delta: time::Duration) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+
         if let AnimationState::Playing(_, elapsed) = self.state {
-            let elapsed = elapsed + delta;
-            let fraction = elapsed.as_micros() / self.delay.as_micros();
-            self.state = AnimationState::Playing(fraction as u64, elapsed);
+            let nanos = delta.as_nanos() as f64 * self.speed.max(0.) as f64;
+            let (from, _) = self.locate(elapsed);
+
+            self.update(elapsed + time::Duration::from_nanos(nanos.round() as u64));
+
+            let (to, finished) = self.locate(self.elapsed());
+            let len = self.pass_len() as u64;
+
+            for step in from + 1..=to {
+                let index = self.frame_at((step % len) as usize);
+                let skipped = step != to;
+
+                if step % len == 0 && !(finished && step == to) {
+                    events.push(AnimationEvent::Looped);
+                }
+                events.push(AnimationEvent::Frame { index, skipped });
+                events.extend(
+                    self.markers
+                        .iter()
+                        .filter(|(frame, _)| *frame == index)
+                        .map(|(_, name)| AnimationEvent::Marker {
+                            name: name.clone(),
+                            index,
+                            skipped,
+                        }),
+                );
+            }
+            if finished {
+                events.push(AnimationEvent::Finished);
+            }
         }
+        events
+    }
+
+    /// Attach a named marker to a frame, triggering an event whenever the
+    /// frame is entered.
+    pub fn mark(&mut self, frame: usize, name: &str) {
+        assert!(frame < self.len(), "frame index out of bounds");
+
+        self.markers.push((frame, name.to_owned()));
+    }
+
+    /// Return the names of the markers attached to a frame.
+    pub fn markers(&self, frame: usize) -> impl Iterator<Item = &str> {
+        self.markers
+            .iter()
+            .filter(move |(f, _)| *f == frame)
+            .map(|(_, name)| name.as_str())
     }
 
+    /// Pause the animation, keeping its position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::Animation;
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&[1, 2, 3], ms(10));
+    ///
+    /// anim.step(ms(10));
+    /// anim.pause();
+    /// anim.step(ms(10));
+    /// assert_eq!(anim.val(), 2);
+    ///
+    /// anim.play();
+    /// anim.step(ms(10));
+    /// assert_eq!(anim.val(), 3);
+    /// ```
     pub fn pause(&mut self) {
-        if let AnimationState::Playing(_, elapsed) = self.state {
-            self.state = AnimationState::Paused(0, elapsed);
+        if let AnimationState::Playing(cursor, elapsed) = self.state {
+            self.state = AnimationState::Paused(cursor, elapsed);
         }
     }
 
+    /// Resume a paused animation, or restart a stopped or finished one.
     pub fn play(&mut self) {
         match self.state {
-            AnimationState::Paused(_, elapsed) => self.state = AnimationState::Playing(0, elapsed),
-            AnimationState::Stopped => {
-                self.state = AnimationState::Playing(0, time::Duration::new(0, 0))
+            AnimationState::Paused(cursor, elapsed) => {
+                self.state = AnimationState::Playing(cursor, elapsed)
+            }
+            AnimationState::Stopped | AnimationState::Finished(_, _) => {
+                self.state = AnimationState::Playing(0, time::Duration::new(0, 0));
+                self.update(time::Duration::new(0, 0));
             }
             _ => {}
         }
@@ -92,6 +376,68 @@ impl<T> Animation<T> {
         self.state = AnimationState::Stopped;
     }
 
+    /// Jump to the first time the given frame is shown in the current pass
+    /// through the animation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::Animation;
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&[1, 2, 3], ms(10));
+    ///
+    /// anim.step(ms(35));
+    /// anim.seek(1);
+    /// assert_eq!(anim.val(), 2);
+    /// assert_eq!(anim.elapsed(), ms(40));
+    /// ```
+    pub fn seek(&mut self, frame: usize) {
+        assert!(frame < self.len(), "frame index out of bounds");
+
+        let len = self.pass_len() as u64;
+        let (step, _) = self.locate(self.elapsed());
+        let pass = match self.playback {
+            Playback::Once => 0,
+            Playback::Times(n) => (step / len).min(n.saturating_sub(1) as u64),
+            Playback::Loop => step / len,
+        };
+        let start = self.pass_duration() * pass as u32;
+        let offset: time::Duration = (0..self.pass_len())
+            .take_while(|s| self.frame_at(*s) != frame)
+            .map(|s| self.duration(self.frame_at(s)))
+            .sum();
+
+        self.seek_time(start + offset);
+    }
+
+    /// Jump to the given playback time. Seeking a stopped animation pauses it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::{Animation, Playback};
+    /// use std::time::Duration;
+    ///
+    /// let ms = Duration::from_millis;
+    /// let mut anim = Animation::new(&[1, 2, 3], ms(10)).playback(Playback::Once);
+    ///
+    /// anim.seek_time(ms(25));
+    /// assert_eq!(anim.val(), 3);
+    /// assert!(anim.is_playing());
+    ///
+    /// anim.seek_time(ms(60));
+    /// assert!(anim.is_finished());
+    /// assert_eq!(anim.elapsed(), ms(30));
+    /// ```
+    pub fn seek_time(&mut self, time: time::Duration) {
+        if let AnimationState::Stopped = self.state {
+            self.state = AnimationState::Paused(0, time);
+        }
+        self.update(time);
+    }
+
     pub fn val(&self) -> T
     where
         T: Copy,
@@ -114,30 +460,162 @@ impl<T> Animation<T> {
         }
     }
 
+    pub fn is_finished(&self) -> bool {
+        match self.state {
+            AnimationState::Finished(_, _) => true,
+            _ => false,
+        }
+    }
+
     pub fn elapsed(&self) -> time::Duration {
         match self.state {
             AnimationState::Playing(_, elapsed) => elapsed,
             AnimationState::Paused(_, elapsed) => elapsed,
+            AnimationState::Finished(_, elapsed) => elapsed,
             AnimationState::Stopped => time::Duration::new(0, 0),
         }
     }
 
+    /// The index of the current frame.
     pub fn cursor(&self) -> u64 {
-        let cursor = match self.state {
+        match self.state {
             AnimationState::Playing(cursor, _) => cursor,
             AnimationState::Paused(cursor, _) => cursor,
-            AnimationState::Stopped => 0,
-        };
-        cursor % self.len() as u64
+            AnimationState::Finished(cursor, _) => cursor,
+            AnimationState::Stopped => self.frame_at(0) as u64,
+        }
+    }
+
+    /// The duration of the given frame.
+    pub fn duration(&self, frame: usize) -> time::Duration {
+        self.durations
+            .get(frame)
+            .cloned()
+            .unwrap_or(None)
+            .unwrap_or(self.delay)
+    }
+
+    /// Set the duration of the given frame.
+    pub fn set_duration(&mut self, frame: usize, duration: time::Duration) {
+        assert!(frame < self.len(), "frame index out of bounds");
+
+        if self.durations.len() < self.len() {
+            self.durations.resize(self.len(), None);
+        }
+        self.durations[frame] = Some(duration);
+    }
+
+    /// The total playback time of the animation, or `None` if it loops forever.
+    pub fn total_duration(&self) -> Option<time::Duration> {
+        match self.playback {
+            Playback::Once => Some(self.pass_duration()),
+            Playback::Times(n) => Some(self.pass_duration() * n),
+            Playback::Loop => None,
+        }
     }
 
     pub fn push_frame(&mut self, frame: T) {
+        self.durations.resize(self.len(), None);
+        self.durations.push(None);
         self.frames.push(frame);
     }
 
+    pub fn push_frame_with_duration(&mut self, frame: T, duration: time::Duration) {
+        let index = self.len();
+
+        self.push_frame(frame);
+        self.durations[index] = Some(duration);
+    }
+
     pub fn pop_frame(&mut self) -> Option<T> {
+        self.durations.truncate(self.len().saturating_sub(1));
         self.frames.pop()
     }
+
+    /// Set the playback time, updating the current frame and finished state.
+    fn update(&mut self, elapsed: time::Duration) {
+        let (step, finished) = self.locate(elapsed);
+        let cursor = self.frame_at((step % self.pass_len().max(1) as u64) as usize) as u64;
+
+        self.state = match self.state {
+            _ if finished => {
+                let total = self.total_duration().unwrap_or(elapsed);
+                AnimationState::Finished(cursor, elapsed.min(total))
+            }
+            AnimationState::Paused(_, _) => AnimationState::Paused(cursor, elapsed),
+            AnimationState::Stopped => AnimationState::Stopped,
+            _ => AnimationState::Playing(cursor, elapsed),
+        };
+    }
+
+    /// Return the number of steps taken since the start of the animation at
+    /// the given playback time, and whether the animation is finished. A
+    /// finished ping-pong animation takes one last step, back to its first
+    /// frame.
+    fn locate(&self, elapsed: time::Duration) -> (u64, bool) {
+        let len = self.pass_len() as u64;
+        if len == 0 {
+            return (0, false);
+        }
+
+        if let Some(total) = self.total_duration() {
+            if elapsed >= total {
+                let passes = match self.playback {
+                    Playback::Times(n) => n as u64,
+                    _ => 1,
+                };
+                let last = match self.direction {
+                    Direction::PingPong if len > 1 => len * passes,
+                    _ => (len * passes).saturating_sub(1),
+                };
+                return (last, true);
+            }
+        }
+
+        let pass = self.pass_duration().as_nanos();
+        if pass == 0 {
+            return (0, false);
+        }
+        let mut t = elapsed.as_nanos() % pass;
+        let mut step = 0;
+
+        for s in 0..len {
+            let d = self.duration(self.frame_at(s as usize)).as_nanos();
+            if t < d {
+                step = s;
+                break;
+            }
+            t -= d;
+        }
+        ((elapsed.as_nanos() / pass) as u64 * len + step, false)
+    }
+
+    /// The number of steps in a single pass through the animation.
+    fn pass_len(&self) -> usize {
+        match (self.direction, self.len()) {
+            (Direction::PingPong, n) if n > 1 => n * 2 - 2,
+            (_, n) => n,
+        }
+    }
+
+    /// The duration of a single pass through the animation.
+    fn pass_duration(&self) -> time::Duration {
+        (0..self.pass_len())
+            .map(|s| self.duration(self.frame_at(s)))
+            .sum()
+    }
+
+    /// The frame shown at the given step of a pass.
+    fn frame_at(&self, step: usize) -> usize {
+        let n = self.len();
+
+        match self.direction {
+            Direction::Forward => step,
+            Direction::Reverse => n.saturating_sub(step + 1),
+            Direction::PingPong if step < n => step,
+            Direction::PingPong => n * 2 - 2 - step,
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
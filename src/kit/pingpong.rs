@@ -0,0 +1,126 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use crate::core;
+use crate::core::{PassOp, TextureFormat};
+
+///////////////////////////////////////////////////////////////////////////
+// PingPong
+///////////////////////////////////////////////////////////////////////////
+
+/// A pair of same-size framebuffers for chaining render passes, where each
+/// pass reads the output of the previous one.
+///
+/// At any time, one framebuffer is the *source*, holding the result of the
+/// last pass, and the other is the *target* of the next pass. Each
+/// framebuffer comes with a binding group sampling from it, created from a
+/// `[SampledTexture, Sampler]` binding group layout, such as the one used by
+/// the `sprite2d` pipeline.
+pub struct PingPong {
+    format: TextureFormat,
+    sampler: core::Sampler,
+    framebuffers: [core::Framebuffer; 2],
+    bindings: [core::BindingGroup; 2],
+    /// Index of the source framebuffer.
+    source: usize,
+}
+
+impl PingPong {
+    pub fn new(
+        r: &core::Renderer,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        layout: &core::BindingGroupLayout,
+        sampler: core::Sampler,
+    ) -> Self {
+        let (framebuffers, bindings) = Self::create(r, w, h, format, layout, &sampler);
+
+        Self {
+            format,
+            sampler,
+            framebuffers,
+            bindings,
+            source: 0,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.framebuffers[0].width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.framebuffers[0].height()
+    }
+
+    /// The framebuffer holding the result of the last pass.
+    pub fn source(&self) -> &core::Framebuffer {
+        &self.framebuffers[self.source]
+    }
+
+    /// The framebuffer the next pass renders to.
+    pub fn target(&self) -> &core::Framebuffer {
+        &self.framebuffers[1 - self.source]
+    }
+
+    /// The binding group sampling from the source framebuffer.
+    pub fn binding(&self) -> &core::BindingGroup {
+        &self.bindings[self.source]
+    }
+
+    /// Make the target the new source, eg. after rendering to it.
+    pub fn swap(&mut self) {
+        self.source = 1 - self.source;
+    }
+
+    /// Render a pass to the target, with the binding group sampling from the
+    /// source, then swap the framebuffers.
+    pub fn step<F>(&mut self, frame: &mut core::Frame, op: PassOp, f: F)
+    where
+        F: FnOnce(&mut core::Pass, &core::BindingGroup),
+    {
+        {
+            let mut pass = frame.pass(op, self.target());
+            f(&mut pass, self.binding());
+        }
+        self.swap();
+    }
+
+    /// Recreate the framebuffers and binding groups with a new size. The
+    /// contents of the framebuffers are lost. Does nothing if the size is
+    /// unchanged.
+    pub fn resize(
+        &mut self,
+        r: &core::Renderer,
+        w: u32,
+        h: u32,
+        layout: &core::BindingGroupLayout,
+    ) {
+        if w == self.width() && h == self.height() {
+            return;
+        }
+        let (framebuffers, bindings) = Self::create(r, w, h, self.format, layout, &self.sampler);
+
+        self.framebuffers = framebuffers;
+        self.bindings = bindings;
+        self.source = 0;
+    }
+
+    fn create(
+        r: &core::Renderer,
+        w: u32,
+        h: u32,
+        format: TextureFormat,
+        layout: &core::BindingGroupLayout,
+        sampler: &core::Sampler,
+    ) -> ([core::Framebuffer; 2], [core::BindingGroup; 2]) {
+        let framebuffers = [
+            r.framebuffer_with(w, h, format),
+            r.framebuffer_with(w, h, format),
+        ];
+        let bindings = [
+            r.binding_group(layout, &[&framebuffers[0], sampler]),
+            r.binding_group(layout, &[&framebuffers[1], sampler]),
+        ];
+        (framebuffers, bindings)
+    }
+}
@@ -0,0 +1,621 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use crate::core;
+use crate::core::{Binding, BindingType, Norm, Rgba, Set, ShaderStage};
+
+use crate::math::Vector2;
+
+///////////////////////////////////////////////////////////////////////////
+// Effect
+///////////////////////////////////////////////////////////////////////////
+
+// Full-screen post-processing effects. Each effect is a pipeline which reads
+// one framebuffer, bound with a binding group from its `binding` method, and
+// renders a full-screen quad into another. Effects don't blend with their
+// target, and should be created with `Blending::constant()`.
+//
+// Effects can be chained with a `PingPong`, by passing `input_layout()` as the
+// binding group layout. The effect parameters are public fields, which are
+// uploaded with `Renderer::update_pipeline`.
+
+crate::vertex! {
+    /// A full-screen quad vertex. Both attributes are normalized, and read
+    /// by the vertex shader as a `vec2`.
+    #[derive(Copy, Clone, Debug)]
+    pub struct Vertex {
+        position: Norm<[i8; 2]>,
+        uv: Norm<[u8; 2]>,
+    }
+}
+
+impl Vertex {
+    const fn new(x: i8, y: i8, u: u8, v: u8) -> Self {
+        Self {
+            position: Norm([x, y]),
+            uv: Norm([u, v]),
+        }
+    }
+}
+
+/// Two triangles covering the viewport. Texture rows are top-down. Positions
+/// of `127` and `-127` are normalized to `1.0` and `-1.0`, and coordinates
+/// of `255` to `1.0`.
+const QUAD: [Vertex; 6] = [
+    Vertex::new(-127, -127, 0, 255),
+    Vertex::new(127, -127, 255, 255),
+    Vertex::new(127, 127, 255, 0),
+    Vertex::new(-127, -127, 0, 255),
+    Vertex::new(-127, 127, 0, 0),
+    Vertex::new(127, 127, 255, 0),
+];
+
+const UNIFORMS: Set<'static> = Set(&[Binding {
+    binding: BindingType::UniformBuffer,
+    stage: ShaderStage::Fragment,
+}]);
+
+const TEXTURE: Set<'static> = Set(&[
+    Binding {
+        binding: BindingType::SampledTexture,
+        stage: ShaderStage::Fragment,
+    },
+    Binding {
+        binding: BindingType::Sampler,
+        stage: ShaderStage::Fragment,
+    },
+]);
+
+/// Uniforms, and the input to be processed.
+const ONE_INPUT: &[Set<'static>] = &[UNIFORMS, TEXTURE];
+/// Uniforms, the input to be processed, and an auxiliary texture.
+const TWO_INPUTS: &[Set<'static>] = &[UNIFORMS, TEXTURE, TEXTURE];
+
+fn description(
+    pipeline_layout: &'static [Set<'static>],
+    fragment_shader: &'static [u8],
+) -> core::PipelineDescription<'static> {
+    core::PipelineDescription {
+        vertex_layout: &[<Vertex as core::Vertex>::LAYOUT],
+        pipeline_layout,
+        vertex_shader: include_bytes!("data/postfx.vert.spv"),
+        fragment_shader,
+        index_format: core::IndexFormat::Uint16,
+    }
+}
+
+/// State shared by all effects.
+struct Effect {
+    pipeline: core::Pipeline<Vertex>,
+    bindings: core::BindingGroup,
+    buf: core::UniformBuffer,
+    quad: core::VertexBuffer<Vertex>,
+    width: u32,
+    height: u32,
+}
+
+impl Effect {
+    fn new<T: Copy + 'static>(
+        pipeline: core::Pipeline<Vertex>,
+        dev: &core::Device,
+        width: u32,
+        height: u32,
+        uniforms: T,
+    ) -> Self {
+        let buf = dev.create_uniform_buffer(&[uniforms]);
+        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&buf]);
+        let quad = dev.create_buffer(&QUAD);
+
+        Self {
+            pipeline,
+            bindings,
+            buf,
+            quad,
+            width,
+            height,
+        }
+    }
+
+    fn binding(
+        &self,
+        set: usize,
+        r: &core::Renderer,
+        input: &dyn core::Bind,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        r.device
+            .create_binding_group(&self.pipeline.layout.sets[set], &[input, sampler])
+    }
+
+    fn apply(&self, pass: &mut core::Pass) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_binding(&self.bindings, &[]);
+    }
+
+    fn draw(&self, pass: &mut core::PipelinePass<Vertex>, inputs: &[&core::BindingGroup]) {
+        for input in inputs {
+            pass.set_binding(input, &[]);
+        }
+        pass.draw_buffer(&self.quad);
+    }
+
+    fn resolution(&self) -> Vector2<f32> {
+        Vector2::new(self.width as f32, self.height as f32)
+    }
+}
+
+/// Implement the parts of an effect which only differ by type: the binding
+/// group for its input, and `AbstractPipeline`. The effect must have an
+/// `effect: Effect` field, a `new` function to set it up with its default
+/// parameters, and a `uniforms` method computing its uniforms from its
+/// parameters and the prepare context.
+macro_rules! effect {
+    ($name:ident, $context:ty, $uniforms:ty, $layout:expr, $shader:literal) => {
+        impl $name {
+            pub fn binding(
+                &self,
+                r: &core::Renderer,
+                input: &core::Framebuffer,
+                sampler: &core::Sampler,
+            ) -> core::BindingGroup {
+                self.effect.binding(1, r, input, sampler)
+            }
+
+            pub fn input_layout(&self) -> &core::BindingGroupLayout {
+                &self.effect.pipeline.layout.sets[1]
+            }
+        }
+
+        impl<'a> core::AbstractPipeline<'a> for $name {
+            type PrepareContext = $context;
+            type Uniforms = $uniforms;
+            type Vertex = Vertex;
+
+            fn description() -> core::PipelineDescription<'a> {
+                description($layout, include_bytes!($shader))
+            }
+
+            fn setup(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+                Self::new(pipeline, dev, w, h)
+            }
+
+            fn apply(&self, pass: &mut core::Pass) {
+                self.effect.apply(pass);
+            }
+
+            fn resize(&mut self, w: u32, h: u32) {
+                self.effect.width = w;
+                self.effect.height = h;
+            }
+
+            fn width(&self) -> u32 {
+                self.effect.width
+            }
+
+            fn height(&self) -> u32 {
+                self.effect.height
+            }
+
+            fn prepare(
+                &'a self,
+                ctx: $context,
+            ) -> Option<(&'a core::UniformBuffer, Vec<$uniforms>)> {
+                Some((&self.effect.buf, vec![self.uniforms(ctx)]))
+            }
+        }
+    };
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Blur
+///////////////////////////////////////////////////////////////////////////
+
+/// The axis along which a separable effect is applied.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct BlurUniforms {
+    /// Distance between samples, in texture coordinates.
+    pub step: Vector2<f32>,
+}
+
+/// A separable 9-tap Gaussian blur. A full blur is done in two passes, one
+/// for each axis.
+pub struct Blur {
+    /// Distance between samples, in texels. Larger values widen the blur at
+    /// the cost of quality.
+    pub spread: f32,
+
+    effect: Effect,
+}
+
+impl Blur {
+    pub fn draw(&self, pass: &mut core::Pass, input: &core::BindingGroup) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let uniforms = BlurUniforms {
+            step: Vector2::new(0., 0.),
+        };
+        Self {
+            spread: 1.,
+            effect: Effect::new(pipeline, dev, w, h, uniforms),
+        }
+    }
+
+    fn uniforms(&self, axis: Axis) -> BlurUniforms {
+        let res = self.effect.resolution();
+        let step = match axis {
+            Axis::X => Vector2::new(self.spread / res.x, 0.),
+            Axis::Y => Vector2::new(0., self.spread / res.y),
+        };
+        BlurUniforms { step }
+    }
+}
+
+effect!(Blur, Axis, BlurUniforms, ONE_INPUT, "data/blur.frag.spv");
+
+///////////////////////////////////////////////////////////////////////////
+// Bloom
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ThresholdUniforms {
+    pub threshold: f32,
+    pub knee: f32,
+}
+
+/// The first step of a bloom: keeps the parts of the input brighter than a
+/// threshold. The result is then blurred with `Blur`, and added back to the
+/// input with `Combine`.
+pub struct Threshold {
+    /// Luminance above which pixels are kept.
+    pub threshold: f32,
+    /// Width of the smooth transition around the threshold.
+    pub knee: f32,
+
+    effect: Effect,
+}
+
+impl Threshold {
+    pub fn draw(&self, pass: &mut core::Pass, input: &core::BindingGroup) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let (threshold, knee) = (0.8, 0.1);
+
+        Self {
+            threshold,
+            knee,
+            effect: Effect::new(pipeline, dev, w, h, ThresholdUniforms { threshold, knee }),
+        }
+    }
+
+    fn uniforms(&self, _: ()) -> ThresholdUniforms {
+        ThresholdUniforms {
+            threshold: self.threshold,
+            knee: self.knee,
+        }
+    }
+}
+
+effect!(
+    Threshold,
+    (),
+    ThresholdUniforms,
+    ONE_INPUT,
+    "data/threshold.frag.spv"
+);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct CombineUniforms {
+    pub intensity: f32,
+}
+
+/// The last step of a bloom: adds the blurred bright parts of a scene back
+/// to the scene. Its `binding` is for the blurred bloom.
+pub struct Combine {
+    /// How much of the bloom is added to the scene.
+    pub intensity: f32,
+
+    effect: Effect,
+}
+
+impl Combine {
+    /// Binding for the original scene.
+    pub fn scene_binding(
+        &self,
+        r: &core::Renderer,
+        scene: &core::Framebuffer,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        self.effect.binding(2, r, scene, sampler)
+    }
+
+    pub fn draw(
+        &self,
+        pass: &mut core::Pass,
+        input: &core::BindingGroup,
+        scene: &core::BindingGroup,
+    ) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input, scene]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let intensity = 1.;
+
+        Self {
+            intensity,
+            effect: Effect::new(pipeline, dev, w, h, CombineUniforms { intensity }),
+        }
+    }
+
+    fn uniforms(&self, _: ()) -> CombineUniforms {
+        CombineUniforms {
+            intensity: self.intensity,
+        }
+    }
+}
+
+effect!(
+    Combine,
+    (),
+    CombineUniforms,
+    TWO_INPUTS,
+    "data/combine.frag.spv"
+);
+
+///////////////////////////////////////////////////////////////////////////
+// ColorGrade
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ColorGradeUniforms {
+    pub size: f32,
+    pub intensity: f32,
+}
+
+/// Color grading with a lookup table (LUT). The LUT is a texture made of
+/// `size` squares of `size` by `size` texels laid out horizontally, one for
+/// each blue value, with red increasing to the right and green downwards.
+/// It should be sampled with linear filtering.
+pub struct ColorGrade {
+    /// Size of the LUT, ie. the height of the LUT texture.
+    pub size: u32,
+    /// How much of the graded color is used, between `0.0` and `1.0`.
+    pub intensity: f32,
+
+    effect: Effect,
+}
+
+impl ColorGrade {
+    pub fn lut_binding(
+        &self,
+        r: &core::Renderer,
+        lut: &core::Texture,
+        sampler: &core::Sampler,
+    ) -> core::BindingGroup {
+        self.effect.binding(2, r, lut, sampler)
+    }
+
+    pub fn draw(
+        &self,
+        pass: &mut core::Pass,
+        input: &core::BindingGroup,
+        lut: &core::BindingGroup,
+    ) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input, lut]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let (size, intensity) = (16, 1.);
+        let uniforms = ColorGradeUniforms {
+            size: size as f32,
+            intensity,
+        };
+
+        Self {
+            size,
+            intensity,
+            effect: Effect::new(pipeline, dev, w, h, uniforms),
+        }
+    }
+
+    fn uniforms(&self, _: ()) -> ColorGradeUniforms {
+        ColorGradeUniforms {
+            size: self.size as f32,
+            intensity: self.intensity,
+        }
+    }
+}
+
+effect!(
+    ColorGrade,
+    (),
+    ColorGradeUniforms,
+    TWO_INPUTS,
+    "data/lut.frag.spv"
+);
+
+///////////////////////////////////////////////////////////////////////////
+// Vignette
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct VignetteUniforms {
+    pub color: Rgba,
+    pub radius: f32,
+    pub softness: f32,
+}
+
+/// Darkens the edges of the image.
+pub struct Vignette {
+    /// Color of the edges. The alpha channel sets the strength of the effect.
+    pub color: Rgba,
+    /// Distance from the center at which the vignette is fully opaque, where
+    /// the corners are at `1.0`.
+    pub radius: f32,
+    /// Width of the transition from the unaffected center.
+    pub softness: f32,
+
+    effect: Effect,
+}
+
+impl Vignette {
+    pub fn draw(&self, pass: &mut core::Pass, input: &core::BindingGroup) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let uniforms = VignetteUniforms {
+            color: Rgba::new(0., 0., 0., 0.75),
+            radius: 1.,
+            softness: 0.6,
+        };
+
+        Self {
+            color: uniforms.color,
+            radius: uniforms.radius,
+            softness: uniforms.softness,
+            effect: Effect::new(pipeline, dev, w, h, uniforms),
+        }
+    }
+
+    fn uniforms(&self, _: ()) -> VignetteUniforms {
+        VignetteUniforms {
+            color: self.color,
+            radius: self.radius,
+            softness: self.softness,
+        }
+    }
+}
+
+effect!(
+    Vignette,
+    (),
+    VignetteUniforms,
+    ONE_INPUT,
+    "data/vignette.frag.spv"
+);
+
+///////////////////////////////////////////////////////////////////////////
+// Pixelate
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PixelateUniforms {
+    pub resolution: Vector2<f32>,
+    pub size: f32,
+}
+
+/// Renders the image with large square pixels.
+pub struct Pixelate {
+    /// Size of the pixels, in texels.
+    pub size: f32,
+
+    effect: Effect,
+}
+
+impl Pixelate {
+    pub fn draw(&self, pass: &mut core::Pass, input: &core::BindingGroup) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let size = 4.;
+        let uniforms = PixelateUniforms {
+            resolution: Vector2::new(w as f32, h as f32),
+            size,
+        };
+
+        Self {
+            size,
+            effect: Effect::new(pipeline, dev, w, h, uniforms),
+        }
+    }
+
+    fn uniforms(&self, _: ()) -> PixelateUniforms {
+        PixelateUniforms {
+            resolution: self.effect.resolution(),
+            size: self.size,
+        }
+    }
+}
+
+effect!(
+    Pixelate,
+    (),
+    PixelateUniforms,
+    ONE_INPUT,
+    "data/pixelate.frag.spv"
+);
+
+///////////////////////////////////////////////////////////////////////////
+// Crt
+///////////////////////////////////////////////////////////////////////////
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct CrtUniforms {
+    pub resolution: Vector2<f32>,
+    pub scanlines: f32,
+    pub curvature: f32,
+}
+
+/// Imitates a CRT monitor, with scanlines and a curved screen.
+pub struct Crt {
+    /// Darkness of the scanlines, between `0.0` and `1.0`.
+    pub scanlines: f32,
+    /// Amount of screen curvature. `0.0` is flat.
+    pub curvature: f32,
+
+    effect: Effect,
+}
+
+impl Crt {
+    pub fn draw(&self, pass: &mut core::Pass, input: &core::BindingGroup) {
+        let mut pass = pass.set_pipeline(self);
+        self.effect.draw(&mut pass, &[input]);
+    }
+
+    fn new(pipeline: core::Pipeline<Vertex>, dev: &core::Device, w: u32, h: u32) -> Self {
+        let uniforms = CrtUniforms {
+            resolution: Vector2::new(w as f32, h as f32),
+            scanlines: 0.25,
+            curvature: 0.05,
+        };
+
+        Self {
+            scanlines: uniforms.scanlines,
+            curvature: uniforms.curvature,
+            effect: Effect::new(pipeline, dev, w, h, uniforms),
+        }
+    }
+
+    fn uniforms(&self, _: ()) -> CrtUniforms {
+        CrtUniforms {
+            resolution: self.effect.resolution(),
+            scanlines: self.scanlines,
+            curvature: self.curvature,
+        }
+    }
+}
+
+effect!(Crt, (), CrtUniforms, ONE_INPUT, "data/crt.frag.spv");
@@ -0,0 +1,227 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::core;
+use crate::core::{
+    AbstractPipeline, Blending, PipelineError, ShaderError, ShaderLanguage, ShaderStage,
+};
+
+///////////////////////////////////////////////////////////////////////////
+// Error
+///////////////////////////////////////////////////////////////////////////
+
+/// An error encountered while loading a shader.
+#[derive(Debug)]
+pub enum Error {
+    /// The shader file couldn't be read.
+    Io(PathBuf, io::Error),
+    /// The shader didn't compile.
+    Compile(PathBuf, ShaderError),
+    /// The shaders don't match the pipeline's description.
+    Pipeline(PipelineError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "error reading {}: {}", path.display(), err),
+            Self::Compile(path, err) => write!(f, "{}:{}", path.display(), err),
+            Self::Pipeline(err) => write!(f, "invalid pipeline: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Compile(_, err) => Some(err),
+            Self::Pipeline(err) => Some(err),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Source
+///////////////////////////////////////////////////////////////////////////
+
+/// A shader source file, and the time it was last loaded at.
+#[derive(Debug)]
+struct Source {
+    path: PathBuf,
+    stage: ShaderStage,
+    modified: Option<SystemTime>,
+}
+
+impl Source {
+    fn new(path: &Path, stage: ShaderStage) -> Self {
+        Self {
+            path: path.to_owned(),
+            stage,
+            modified: None,
+        }
+    }
+
+    /// The language of the source, from its file extension. Files which
+    /// aren't WGSL are assumed to be GLSL, eg. `.vert` and `.frag` files.
+    fn language(&self) -> ShaderLanguage {
+        match self.path.extension().and_then(|e| e.to_str()) {
+            Some("wgsl") => ShaderLanguage::Wgsl,
+            _ => ShaderLanguage::Glsl,
+        }
+    }
+
+    fn is_modified(&self) -> bool {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified());
+
+        match (modified, self.modified) {
+            (Ok(modified), Some(loaded)) => modified != loaded,
+            // Let errors be reported when the file is read.
+            _ => true,
+        }
+    }
+
+    fn compile(&mut self, r: &core::Renderer) -> Result<core::Shader, Error> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok();
+        let source =
+            std::fs::read_to_string(&self.path).map_err(|err| Error::Io(self.path.clone(), err))?;
+        let name = self.path.to_string_lossy();
+
+        // Don't try to reload the same file again if it fails to compile.
+        self.modified = modified;
+
+        r.device
+            .compile_shader(&name, &source, self.language(), self.stage)
+            .map_err(|err| Error::Compile(self.path.clone(), err))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Reloadable
+///////////////////////////////////////////////////////////////////////////
+
+/// A pipeline built from shader source files, which is rebuilt when the
+/// files change. This is meant for development, to iterate on shaders
+/// without restarting.
+///
+/// The pipeline's description is used for everything but the shaders.
+/// Rebuilding the pipeline calls its `setup` function, so any state it
+/// holds besides its size is reset.
+///
+/// Dereferences to the pipeline, which can be used with eg.
+/// `pass.set_pipeline(&*pipeline)`.
+pub struct Reloadable<T> {
+    pipeline: T,
+    blending: Blending,
+    format: core::TextureFormat,
+    vertex: Source,
+    fragment: Source,
+}
+
+impl<T> Reloadable<T>
+where
+    T: AbstractPipeline<'static>,
+{
+    /// Build a pipeline rendering to the swap chain from the given shader
+    /// files.
+    pub fn new<P: AsRef<Path>>(
+        r: &core::Renderer,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        vertex: P,
+        fragment: P,
+    ) -> Result<Self, Error> {
+        Self::with_format(
+            r,
+            w,
+            h,
+            blending,
+            core::TextureFormat::Bgra8,
+            vertex,
+            fragment,
+        )
+    }
+
+    /// Build a pipeline rendering to targets of the given format from the
+    /// given shader files.
+    pub fn with_format<P: AsRef<Path>>(
+        r: &core::Renderer,
+        w: u32,
+        h: u32,
+        blending: Blending,
+        format: core::TextureFormat,
+        vertex: P,
+        fragment: P,
+    ) -> Result<Self, Error> {
+        let mut vertex = Source::new(vertex.as_ref(), ShaderStage::Vertex);
+        let mut fragment = Source::new(fragment.as_ref(), ShaderStage::Fragment);
+
+        let vs = vertex.compile(r)?;
+        let fs = fragment.compile(r)?;
+        let pipeline = r
+            .try_pipeline_with_shaders(w, h, blending.clone(), format, &vs, &fs)
+            .map_err(Error::Pipeline)?;
+
+        Ok(Self {
+            pipeline,
+            blending,
+            format,
+            vertex,
+            fragment,
+        })
+    }
+
+    /// Rebuild the pipeline if any of its shader files changed since they
+    /// were last loaded. Returns whether the pipeline was rebuilt. If the
+    /// shaders fail to load or don't match the pipeline's description, the
+    /// previous pipeline is kept, and the error is returned. This is cheap
+    /// enough to be called every frame.
+    pub fn reload(&mut self, r: &core::Renderer) -> Result<bool, Error> {
+        if !self.vertex.is_modified() && !self.fragment.is_modified() {
+            return Ok(false);
+        }
+        // Compile both shaders before checking for errors, so that both
+        // files are marked as loaded.
+        let vs = self.vertex.compile(r);
+        let fs = self.fragment.compile(r);
+        let (vs, fs) = (vs?, fs?);
+
+        self.pipeline = r
+            .try_pipeline_with_shaders(
+                self.pipeline.width(),
+                self.pipeline.height(),
+                self.blending.clone(),
+                self.format,
+                &vs,
+                &fs,
+            )
+            .map_err(Error::Pipeline)?;
+        Ok(true)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.pipeline
+    }
+}
+
+impl<T> Deref for Reloadable<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.pipeline
+    }
+}
+
+impl<T> DerefMut for Reloadable<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.pipeline
+    }
+}
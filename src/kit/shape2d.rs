@@ -26,11 +26,12 @@ pub struct Uniforms {
 // Vertex
 ///////////////////////////////////////////////////////////////////////////
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug)]
-pub struct Vertex {
-    position: Vector2<f32>,
-    color: Rgba8,
+crate::vertex! {
+    #[derive(Copy, Clone, Debug)]
+    pub struct Vertex {
+        position: Vector2<f32>,
+        color: Rgba8,
+    }
 }
 
 impl Vertex {
@@ -52,7 +53,7 @@ pub const fn vertex(x: f32, y: f32, color: Rgba8) -> Vertex {
 ///////////////////////////////////////////////////////////////////////////
 
 pub struct Pipeline {
-    pipeline: core::Pipeline,
+    pipeline: core::Pipeline<Vertex>,
     bindings: core::BindingGroup,
     buf: core::UniformBuffer,
     width: u32,
@@ -66,10 +67,11 @@ pub struct Pipeline {
 impl<'a> core::AbstractPipeline<'a> for Pipeline {
     type PrepareContext = Matrix4<f32>;
     type Uniforms = self::Uniforms;
+    type Vertex = Vertex;
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[core::VertexFormat::Float2, core::VertexFormat::UByte4],
+            vertex_layout: &[<Self::Vertex as core::Vertex>::LAYOUT],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -83,10 +85,16 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/shape.vert.spv"),
             fragment_shader: include_bytes!("data/shape.frag.spv"),
+            index_format: core::IndexFormat::Uint16,
         }
     }
 
-    fn setup(pipeline: core::Pipeline, dev: &core::Device, width: u32, height: u32) -> Self {
+    fn setup(
+        pipeline: core::Pipeline<Vertex>,
+        dev: &core::Device,
+        width: u32,
+        height: u32,
+    ) -> Self {
         let ortho = kit::ortho(width, height);
         let transform = Matrix4::identity();
         let model = Model::new(&pipeline.layout.sets[1], &[Matrix4::identity()], dev);
@@ -381,12 +389,12 @@ impl Batch {
         self.items.clear();
     }
 
-    pub fn buffer(&self, r: &core::Renderer) -> core::VertexBuffer {
+    pub fn buffer(&self, r: &core::Renderer) -> core::VertexBuffer<Vertex> {
         let buf = self.vertices();
         r.device.create_buffer(buf.as_slice())
     }
 
-    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
+    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer<Vertex> {
         self.buffer(r)
     }
 }
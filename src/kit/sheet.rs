@@ -0,0 +1,494 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::fmt;
+use std::time;
+
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+
+use crate::core::{Rect, Rgba};
+
+use crate::kit::sprite2d;
+use crate::kit::{Animation, Direction, Playback};
+
+///////////////////////////////////////////////////////////////////////////
+// Error
+///////////////////////////////////////////////////////////////////////////
+
+/// An error encountered while loading a sprite sheet.
+#[derive(Debug)]
+pub enum Error {
+    /// The sprite sheet isn't valid JSON, or is missing required fields.
+    Json(serde_json::Error),
+    /// The sprite sheet is well-formed, but inconsistent.
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "invalid sprite sheet: {}", e),
+            Self::Invalid(s) => write!(f, "invalid sprite sheet: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Frame
+///////////////////////////////////////////////////////////////////////////
+
+/// A single frame of a sprite sheet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The frame's file name, or name in the packer.
+    pub name: String,
+    /// The region of the texture holding the frame. For rotated frames, this
+    /// is the rotated region, ie. its width is the height of the sprite.
+    pub rect: Rect<f32>,
+    /// Whether the frame is stored rotated 90 degrees clockwise.
+    pub rotated: bool,
+    /// The region of the original sprite left after trimming transparent
+    /// pixels, relative to the top-left corner of the sprite. Equal to the
+    /// whole sprite if the frame isn't trimmed.
+    pub trim: Rect<f32>,
+    /// Width of the original, untrimmed sprite.
+    pub w: f32,
+    /// Height of the original, untrimmed sprite.
+    pub h: f32,
+    /// How long the frame is shown for, if known.
+    pub duration: Option<time::Duration>,
+}
+
+impl Frame {
+    pub fn is_trimmed(&self) -> bool {
+        self.trim != Rect::origin(self.w, self.h)
+    }
+
+    /// Return where the trimmed frame should be drawn, for the untrimmed
+    /// sprite to be drawn into `dst`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rect;
+    /// use rgx::kit::sheet::Frame;
+    ///
+    /// let frame = Frame {
+    ///     name: String::from("coin"),
+    ///     rect: Rect::new(0., 0., 8., 6.),
+    ///     rotated: false,
+    ///     trim: Rect::new(4., 2., 12., 8.),
+    ///     w: 16.,
+    ///     h: 16.,
+    ///     duration: None,
+    /// };
+    /// // Sprite coordinates have the Y axis pointing up.
+    /// assert_eq!(
+    ///     frame.dst(Rect::new(100., 100., 132., 132.)),
+    ///     Rect::new(108., 116., 124., 128.)
+    /// );
+    /// ```
+    pub fn dst(&self, dst: Rect<f32>) -> Rect<f32> {
+        let sx = dst.width() / self.w;
+        let sy = dst.height() / self.h;
+        let top = dst.y1.max(dst.y2);
+        let left = dst.x1.min(dst.x2);
+
+        Rect::new(
+            left + self.trim.x1 * sx,
+            top - self.trim.y2 * sy,
+            left + self.trim.x2 * sx,
+            top - self.trim.y1 * sy,
+        )
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Tag
+///////////////////////////////////////////////////////////////////////////
+
+/// A named range of frames, eg. an animation clip.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    /// Index of the first frame.
+    pub from: usize,
+    /// Index of the last frame, inclusive.
+    pub to: usize,
+    pub direction: Direction,
+    /// Whether the frames are played in reverse order.
+    pub reversed: bool,
+    pub playback: Playback,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// SpriteSheet
+///////////////////////////////////////////////////////////////////////////
+
+/// A sprite sheet, as exported by Aseprite or TexturePacker in their JSON
+/// formats. Both the "hash" and "array" variants are supported.
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    /// The sprite sheet texture's file name.
+    pub image: Option<String>,
+    /// Width of the sprite sheet texture.
+    pub w: u32,
+    /// Height of the sprite sheet texture.
+    pub h: u32,
+    pub frames: Vec<Frame>,
+    pub tags: Vec<Tag>,
+}
+
+impl SpriteSheet {
+    /// Duration of frames which don't specify one.
+    pub const DEFAULT_DURATION: time::Duration = time::Duration::from_millis(100);
+
+    /// Parse a JSON sprite sheet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rect;
+    /// use rgx::kit::sheet::SpriteSheet;
+    /// use std::time::Duration;
+    ///
+    /// let json = r#"{
+    ///   "frames": {
+    ///     "hero 0.aseprite": {
+    ///       "frame": { "x": 0, "y": 0, "w": 16, "h": 16 },
+    ///       "rotated": false,
+    ///       "trimmed": false,
+    ///       "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+    ///       "sourceSize": { "w": 16, "h": 16 },
+    ///       "duration": 100
+    ///     },
+    ///     "hero 1.aseprite": {
+    ///       "frame": { "x": 16, "y": 0, "w": 16, "h": 16 },
+    ///       "rotated": false,
+    ///       "trimmed": false,
+    ///       "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+    ///       "sourceSize": { "w": 16, "h": 16 },
+    ///       "duration": 250
+    ///     }
+    ///   },
+    ///   "meta": {
+    ///     "app": "http://www.aseprite.org/",
+    ///     "image": "hero.png",
+    ///     "size": { "w": 32, "h": 16 },
+    ///     "frameTags": [
+    ///       { "name": "walk", "from": 0, "to": 1, "direction": "forward" }
+    ///     ]
+    ///   }
+    /// }"#;
+    ///
+    /// let sheet = SpriteSheet::parse(json).unwrap();
+    /// assert_eq!(sheet.frames[1].rect, Rect::new(16., 0., 32., 16.));
+    ///
+    /// let mut walk = sheet.animation("walk").unwrap();
+    /// walk.step(Duration::from_millis(100));
+    /// assert_eq!(walk.val(), 1);
+    /// assert_eq!(walk.duration(1), Duration::from_millis(250));
+    /// ```
+    pub fn parse(json: &str) -> Result<Self, Error> {
+        let sheet: json::Sheet = serde_json::from_str(json)?;
+        let frames = match sheet.frames {
+            json::Frames::Hash(json::Ordered(frames)) => frames,
+            json::Frames::Array(frames) => frames
+                .into_iter()
+                .map(|f| (f.filename.clone().unwrap_or_default(), f))
+                .collect(),
+        };
+        let frames: Vec<Frame> = frames
+            .into_iter()
+            .map(|(name, f)| f.into_frame(name))
+            .collect();
+
+        let mut tags = Vec::with_capacity(sheet.meta.frame_tags.len());
+        for t in sheet.meta.frame_tags {
+            if t.from > t.to || t.to >= frames.len() {
+                return Err(Error::Invalid(format!(
+                    "tag `{}` has invalid frame range {}..{}",
+                    t.name, t.from, t.to
+                )));
+            }
+            tags.push(t.into_tag()?);
+        }
+
+        let (w, h) = match sheet.meta.size {
+            Some(size) => (size.w as u32, size.h as u32),
+            None => frames.iter().fold((0, 0), |(w, h), f| {
+                (
+                    u32::max(w, f.rect.x2.ceil() as u32),
+                    u32::max(h, f.rect.y2.ceil() as u32),
+                )
+            }),
+        };
+
+        Ok(Self {
+            image: sheet.meta.image,
+            w,
+            h,
+            frames,
+            tags,
+        })
+    }
+
+    /// Return the index of the frame with the given name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rect;
+    /// use rgx::kit::sheet::SpriteSheet;
+    ///
+    /// let json = r#"{
+    ///   "frames": [
+    ///     {
+    ///       "filename": "gem.png",
+    ///       "frame": { "x": 2, "y": 2, "w": 10, "h": 6 },
+    ///       "rotated": true,
+    ///       "trimmed": true,
+    ///       "spriteSourceSize": { "x": 3, "y": 5, "w": 10, "h": 6 },
+    ///       "sourceSize": { "w": 16, "h": 16 }
+    ///     }
+    ///   ],
+    ///   "meta": { "app": "https://www.codeandweb.com/texturepacker", "size": { "w": 64, "h": 64 } }
+    /// }"#;
+    ///
+    /// let sheet = SpriteSheet::parse(json).unwrap();
+    /// let gem = &sheet.frames[sheet.frame("gem.png").unwrap()];
+    ///
+    /// assert!(gem.rotated);
+    /// assert!(gem.is_trimmed());
+    /// assert_eq!(gem.rect, Rect::new(2., 2., 8., 12.));
+    /// assert_eq!(gem.trim, Rect::new(3., 5., 13., 11.));
+    /// ```
+    pub fn frame(&self, name: &str) -> Option<usize> {
+        self.frames.iter().position(|f| f.name == name)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|t| t.name == name)
+    }
+
+    /// Create an animation of frame indices from the named tag, with the
+    /// durations of each frame.
+    pub fn animation(&self, tag: &str) -> Option<Animation<usize>> {
+        self.tag(tag).map(|t| {
+            let mut frames: Vec<(usize, time::Duration)> = (t.from..=t.to)
+                .map(|i| {
+                    let duration = self.frames[i].duration;
+                    (i, duration.unwrap_or(Self::DEFAULT_DURATION))
+                })
+                .collect();
+
+            if t.reversed {
+                frames.reverse();
+            }
+            Animation::timed(&frames)
+                .direction(t.direction)
+                .playback(t.playback)
+        })
+    }
+
+    /// Create animations for all tags, in order.
+    pub fn animations(&self) -> Vec<(&str, Animation<usize>)> {
+        self.tags
+            .iter()
+            .filter_map(|t| self.animation(&t.name).map(|a| (t.name.as_str(), a)))
+            .collect()
+    }
+
+    /// Add a frame to a batch. `dst` is where the untrimmed, upright sprite
+    /// should be drawn.
+    pub fn add(
+        &self,
+        batch: &mut sprite2d::Batch,
+        frame: usize,
+        dst: Rect<f32>,
+        rgba: Rgba,
+        opacity: f32,
+    ) {
+        let f = &self.frames[frame];
+        let dst = f.dst(dst);
+
+        if f.rotated {
+            batch.add_rotated(f.rect, dst, rgba, opacity);
+        } else {
+            batch.add(f.rect, dst, rgba, opacity, Default::default());
+        }
+    }
+
+    /// Create an empty batch for this sprite sheet.
+    pub fn batch(&self) -> sprite2d::Batch {
+        sprite2d::Batch::new(self.w, self.h)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// JSON
+///////////////////////////////////////////////////////////////////////////
+
+mod json {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct Sheet {
+        pub frames: Frames,
+        #[serde(default)]
+        pub meta: Meta,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum Frames {
+        Array(Vec<Frame>),
+        Hash(Ordered<Frame>),
+    }
+
+    #[derive(Deserialize, Default)]
+    pub struct Meta {
+        pub image: Option<String>,
+        pub size: Option<Size>,
+        #[serde(rename = "frameTags", default)]
+        pub frame_tags: Vec<Tag>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Frame {
+        pub filename: Option<String>,
+        pub frame: Region,
+        #[serde(default)]
+        pub rotated: bool,
+        #[serde(rename = "spriteSourceSize")]
+        pub sprite_source_size: Option<Region>,
+        #[serde(rename = "sourceSize")]
+        pub source_size: Option<Size>,
+        pub duration: Option<u64>,
+    }
+
+    impl Frame {
+        pub fn into_frame(self, name: String) -> super::Frame {
+            let Region { x, y, w, h } = self.frame;
+            // Packers give the size of the upright sprite, even when rotated.
+            let rect = if self.rotated {
+                Rect::new(x, y, x + h, y + w)
+            } else {
+                Rect::new(x, y, x + w, y + h)
+            };
+            let trim = self
+                .sprite_source_size
+                .map(|r| Rect::new(r.x, r.y, r.x + r.w, r.y + r.h))
+                .unwrap_or_else(|| Rect::origin(w, h));
+            let source = self.source_size.unwrap_or(Size { w, h });
+
+            super::Frame {
+                name,
+                rect,
+                rotated: self.rotated,
+                trim,
+                w: source.w,
+                h: source.h,
+                duration: self.duration.map(time::Duration::from_millis),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Tag {
+        pub name: String,
+        pub from: usize,
+        pub to: usize,
+        #[serde(default)]
+        pub direction: String,
+        pub repeat: Option<serde_json::Value>,
+    }
+
+    impl Tag {
+        pub fn into_tag(self) -> Result<super::Tag, Error> {
+            let (direction, reversed) = match self.direction.as_str() {
+                "" | "forward" => (Direction::Forward, false),
+                "reverse" => (Direction::Reverse, false),
+                "pingpong" => (Direction::PingPong, false),
+                "pingpong_reverse" => (Direction::PingPong, true),
+                other => {
+                    return Err(Error::Invalid(format!(
+                        "tag `{}` has unknown direction `{}`",
+                        self.name, other
+                    )))
+                }
+            };
+            // Aseprite exports the repeat count as a string.
+            let repeat = match &self.repeat {
+                Some(serde_json::Value::String(s)) => s.parse::<u32>().ok(),
+                Some(serde_json::Value::Number(n)) => n.as_u64().map(|n| n as u32),
+                _ => None,
+            };
+            let playback = match repeat {
+                None | Some(0) => Playback::Loop,
+                Some(1) => Playback::Once,
+                Some(n) => Playback::Times(n),
+            };
+
+            Ok(super::Tag {
+                name: self.name,
+                from: self.from,
+                to: self.to,
+                direction,
+                reversed,
+                playback,
+            })
+        }
+    }
+
+    #[derive(Deserialize, Clone, Copy)]
+    pub struct Region {
+        pub x: f32,
+        pub y: f32,
+        pub w: f32,
+        pub h: f32,
+    }
+
+    #[derive(Deserialize, Clone, Copy)]
+    pub struct Size {
+        pub w: f32,
+        pub h: f32,
+    }
+
+    /// A JSON object, with its entries in document order. The order of
+    /// frames in the "hash" format is significant, as tags refer to frames
+    /// by index.
+    pub struct Ordered<T>(pub Vec<(String, T)>);
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for Ordered<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct OrderedVisitor<T>(std::marker::PhantomData<T>);
+
+            impl<'de, T: Deserialize<'de>> Visitor<'de> for OrderedVisitor<T> {
+                type Value = Ordered<T>;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "a map")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+
+                    while let Some(entry) = map.next_entry()? {
+                        entries.push(entry);
+                    }
+                    Ok(Ordered(entries))
+                }
+            }
+            deserializer.deserialize_map(OrderedVisitor(std::marker::PhantomData))
+        }
+    }
+}
@@ -26,13 +26,14 @@ pub struct Uniforms {
 // Vertex
 ///////////////////////////////////////////////////////////////////////////
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug)]
-pub struct Vertex {
-    position: Vector2<f32>,
-    uv: Vector2<f32>,
-    color: Rgba8,
-    opacity: f32,
+crate::vertex! {
+    #[derive(Copy, Clone, Debug)]
+    pub struct Vertex {
+        position: Vector2<f32>,
+        uv: Vector2<f32>,
+        color: Rgba8,
+        opacity: f32,
+    }
 }
 
 impl Vertex {
@@ -51,7 +52,7 @@ impl Vertex {
 ///////////////////////////////////////////////////////////////////////////
 
 pub struct Pipeline {
-    pipeline: core::Pipeline,
+    pipeline: core::Pipeline<Vertex>,
     bindings: core::BindingGroup,
     buf: core::UniformBuffer,
     width: u32,
@@ -75,7 +76,11 @@ impl Pipeline {
 
 //////////////////////////////////////////////////////////////////////////
 
-pub struct Command<'a>(&'a core::VertexBuffer, &'a core::BindingGroup, Matrix4<f32>);
+pub struct Command<'a>(
+    &'a core::VertexBuffer<Vertex>,
+    &'a core::BindingGroup,
+    Matrix4<f32>,
+);
 
 pub struct Frame<'a> {
     commands: Vec<Command<'a>>,
@@ -83,7 +88,11 @@ pub struct Frame<'a> {
 }
 
 impl<'a> Frame<'a> {
-    pub fn draw(&mut self, buffer: &'a core::VertexBuffer, binding: &'a core::BindingGroup) {
+    pub fn draw(
+        &mut self,
+        buffer: &'a core::VertexBuffer<Vertex>,
+        binding: &'a core::BindingGroup,
+    ) {
         self.commands
             .push(Command(buffer, binding, *self.transforms.last()));
     }
@@ -117,15 +126,11 @@ impl<'a> Frame<'a> {
 impl<'a> core::AbstractPipeline<'a> for Pipeline {
     type PrepareContext = Matrix4<f32>;
     type Uniforms = self::Uniforms;
+    type Vertex = Vertex;
 
     fn description() -> core::PipelineDescription<'a> {
         core::PipelineDescription {
-            vertex_layout: &[
-                core::VertexFormat::Float2,
-                core::VertexFormat::Float2,
-                core::VertexFormat::UByte4,
-                core::VertexFormat::Float,
-            ],
+            vertex_layout: &[<Self::Vertex as core::Vertex>::LAYOUT],
             pipeline_layout: &[
                 Set(&[Binding {
                     binding: BindingType::UniformBuffer,
@@ -149,10 +154,16 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
             // TODO: Use `env("CARGO_MANIFEST_DIR")`
             vertex_shader: include_bytes!("data/sprite.vert.spv"),
             fragment_shader: include_bytes!("data/sprite.frag.spv"),
+            index_format: core::IndexFormat::Uint16,
         }
     }
 
-    fn setup(pipeline: core::Pipeline, dev: &core::Device, width: u32, height: u32) -> Self {
+    fn setup(
+        pipeline: core::Pipeline<Vertex>,
+        dev: &core::Device,
+        width: u32,
+        height: u32,
+    ) -> Self {
         let ortho = kit::ortho(width, height);
         let transform = Matrix4::identity();
         let model = Model::new(&pipeline.layout.sets[1], &[Matrix4::identity()], dev);
@@ -208,13 +219,17 @@ impl<'a> core::AbstractPipeline<'a> for Pipeline {
 /// Batch
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// A batched sprite: source and destination rectangles, color, opacity,
+/// texture repeat, and whether the source is rotated.
+type Item = (Rect<f32>, Rect<f32>, Rgba, f32, Repeat, bool);
+
 #[derive(Clone, Debug)]
 pub struct Batch {
     pub w: u32,
     pub h: u32,
     pub size: usize,
 
-    items: Vec<(Rect<f32>, Rect<f32>, Rgba, f32, Repeat)>,
+    items: Vec<Item>,
 }
 
 impl Batch {
@@ -242,6 +257,8 @@ impl Batch {
     }
 
     pub fn add(&mut self, src: Rect<f32>, dst: Rect<f32>, rgba: Rgba, opacity: f32, rep: Repeat) {
+        self.check_src(src);
+
         if rep != Repeat::default() {
             assert!(
                 src == Rect::origin(self.w as f32, self.h as f32),
@@ -250,14 +267,38 @@ impl Batch {
                 self.h
             );
         }
-        self.items.push((src, dst, rgba, opacity, rep));
+        self.items.push((src, dst, rgba, opacity, rep, false));
         self.size += 1;
     }
 
+    /// Add a sprite stored rotated 90 degrees clockwise in the `src` region
+    /// of the texture, as done by sprite sheet packers. The sprite is drawn
+    /// upright into `dst`.
+    pub fn add_rotated(&mut self, src: Rect<f32>, dst: Rect<f32>, rgba: Rgba, opacity: f32) {
+        self.check_src(src);
+
+        self.items
+            .push((src, dst, rgba, opacity, Repeat::default(), true));
+        self.size += 1;
+    }
+
+    /// Panic if the `src` region isn't inside the texture.
+    fn check_src(&self, src: Rect<f32>) {
+        let (x1, x2) = (src.x1.min(src.x2), src.x1.max(src.x2));
+        let (y1, y2) = (src.y1.min(src.y2), src.y1.max(src.y2));
+        assert!(
+            x1 >= 0. && y1 >= 0. && x2 <= self.w as f32 && y2 <= self.h as f32,
+            "source region {:?} is outside of the {}x{} texture",
+            src,
+            self.w,
+            self.h
+        );
+    }
+
     pub fn vertices(&self) -> Vec<Vertex> {
         let mut buf = Vec::with_capacity(6 * self.items.len());
 
-        for (src, dst, rgba, o, rep) in self.items.iter() {
+        for (src, dst, rgba, o, rep, rotated) in self.items.iter() {
             // Relative texture coordinates
             let rx1: f32 = src.x1 / self.w as f32;
             let ry1: f32 = src.y1 / self.h as f32;
@@ -266,6 +307,20 @@ impl Batch {
 
             let c: Rgba8 = (*rgba).into();
 
+            if *rotated {
+                // The top-left corner of the sprite is at the top-right of
+                // the source region.
+                buf.extend_from_slice(&[
+                    Vertex::new(dst.x1, dst.y1, rx1, ry1, c, *o),
+                    Vertex::new(dst.x2, dst.y1, rx1, ry2, c, *o),
+                    Vertex::new(dst.x2, dst.y2, rx2, ry2, c, *o),
+                    Vertex::new(dst.x1, dst.y1, rx1, ry1, c, *o),
+                    Vertex::new(dst.x1, dst.y2, rx2, ry1, c, *o),
+                    Vertex::new(dst.x2, dst.y2, rx2, ry2, c, *o),
+                ]);
+                continue;
+            }
+
             // TODO: Use an index buffer
             buf.extend_from_slice(&[
                 Vertex::new(dst.x1, dst.y1, rx1 * rep.x, ry2 * rep.y, c, *o),
@@ -279,7 +334,7 @@ impl Batch {
         buf
     }
 
-    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer {
+    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer<Vertex> {
         let buf = self.vertices();
         r.device.create_buffer(buf.as_slice())
     }
@@ -290,7 +345,7 @@ impl Batch {
     }
 
     pub fn offset(&mut self, x: f32, y: f32) {
-        for (_, dst, _, _, _) in self.items.iter_mut() {
+        for (_, dst, _, _, _, _) in self.items.iter_mut() {
             *dst = *dst + Vector2::new(x, y);
         }
     }
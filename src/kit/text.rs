@@ -0,0 +1,762 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::core;
+use crate::core::{Rect, Rgba};
+
+use crate::kit::sprite2d;
+use crate::kit::Repeat;
+
+/// Maximum number of atlas pages in a font. Glyphs in the binary format
+/// store their page as a single byte.
+const MAX_PAGES: usize = 256;
+
+///////////////////////////////////////////////////////////////////////////
+// Error
+///////////////////////////////////////////////////////////////////////////
+
+/// An error encountered while loading a font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The font data ended before it was expected to.
+    UnexpectedEof,
+    /// The binary font data is of an unsupported version.
+    UnsupportedVersion(u8),
+    /// A line or block of the font data couldn't be parsed.
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of font data"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported font version {}", v),
+            Self::Invalid(s) => write!(f, "invalid font data: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+///////////////////////////////////////////////////////////////////////////
+// Glyph
+///////////////////////////////////////////////////////////////////////////
+
+/// A single glyph in a font atlas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glyph {
+    /// Region of the glyph in its atlas page, in pixels.
+    pub rect: Rect<f32>,
+    /// Horizontal offset from the pen position to the glyph.
+    pub x_offset: f32,
+    /// Vertical offset from the top of the line to the glyph.
+    pub y_offset: f32,
+    /// How far to move the pen after drawing the glyph.
+    pub advance: f32,
+    /// Atlas page the glyph is on.
+    pub page: u32,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Font
+///////////////////////////////////////////////////////////////////////////
+
+/// A bitmap font, in the AngelCode BMFont format.
+#[derive(Clone, Debug)]
+pub struct Font {
+    /// Font face name.
+    pub name: String,
+    /// Font size the atlas was rendered at.
+    pub size: f32,
+    /// Distance between two lines of text.
+    pub line_height: f32,
+    /// Distance from the top of a line to the baseline.
+    pub base: f32,
+    /// Width of an atlas page, in pixels.
+    pub w: u32,
+    /// Height of an atlas page, in pixels.
+    pub h: u32,
+    /// Image file names of the atlas pages, indexed by page.
+    pub pages: Vec<String>,
+
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), f32>,
+}
+
+impl Font {
+    /// Parse a font from either the text or binary BMFont format.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.starts_with(b"BMF") {
+            Self::from_binary(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes)
+                .map_err(|_| Error::Invalid(String::from("font data is not valid UTF-8")))?;
+            Self::from_text(text)
+        }
+    }
+
+    /// Parse a font from the BMFont text format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::text::Font;
+    ///
+    /// let font = Font::from_text(
+    ///     "info face=\"Mono\" size=8\n\
+    ///      common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1\n\
+    ///      page id=0 file=\"mono.png\"\n\
+    ///      char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=0 xadvance=7 page=0\n\
+    ///      kerning first=65 second=65 amount=-1\n",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(font.name, "Mono");
+    /// assert_eq!(font.glyph('A').unwrap().advance, 7.);
+    /// assert_eq!(font.kerning('A', 'A'), -1.);
+    ///
+    /// assert!(Font::from_text("page id=-1 file=\"mono.png\"").is_err());
+    /// ```
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        let mut font = Self::empty();
+
+        for line in text.lines() {
+            let mut tokens = Tokens::new(line);
+
+            let tag = match tokens.next() {
+                Some((tag, None)) => tag,
+                Some(_) => return Err(Error::Invalid(line.to_owned())),
+                None => continue,
+            };
+            let attrs = Attrs {
+                line,
+                map: tokens.map(|(k, v)| (k, v.unwrap_or(""))).collect(),
+            };
+
+            match tag {
+                "info" => {
+                    font.name = attrs.map.get("face").cloned().unwrap_or("").to_owned();
+                    font.size = (attrs.num("size")? as f32).abs();
+                }
+                "common" => {
+                    font.line_height = attrs.num("lineHeight")? as f32;
+                    font.base = attrs.num("base")? as f32;
+                    font.w = attrs.unsigned("scaleW")?;
+                    font.h = attrs.unsigned("scaleH")?;
+                }
+                "page" => {
+                    let id = attrs.unsigned("id")? as usize;
+                    if id >= MAX_PAGES {
+                        return Err(Error::Invalid(format!(
+                            "page {} out of range in `{}`",
+                            id, line
+                        )));
+                    }
+                    if font.pages.len() <= id {
+                        font.pages.resize(id + 1, String::new());
+                    }
+                    font.pages[id] = attrs.get("file")?.to_owned();
+                }
+                "char" => {
+                    let c = to_char(attrs.num("id")? as u32, line)?;
+                    let (x, y) = (attrs.num("x")? as f32, attrs.num("y")? as f32);
+
+                    font.glyphs.insert(
+                        c,
+                        Glyph {
+                            rect: Rect::new(
+                                x,
+                                y,
+                                x + attrs.num("width")? as f32,
+                                y + attrs.num("height")? as f32,
+                            ),
+                            x_offset: attrs.num("xoffset")? as f32,
+                            y_offset: attrs.num("yoffset")? as f32,
+                            advance: attrs.num("xadvance")? as f32,
+                            page: attrs.map.get("page").map_or(Ok(0), |p| parse(p, line))?,
+                        },
+                    );
+                }
+                "kerning" => {
+                    let first = to_char(attrs.num("first")? as u32, line)?;
+                    let second = to_char(attrs.num("second")? as u32, line)?;
+
+                    font.kerning
+                        .insert((first, second), attrs.num("amount")? as f32);
+                }
+                _ => {}
+            }
+        }
+        Ok(font)
+    }
+
+    /// Parse a font from the BMFont binary format, version 3.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader(bytes);
+
+        if r.bytes(3)? != b"BMF" {
+            return Err(Error::Invalid(String::from("missing `BMF` header")));
+        }
+        let version = r.u8()?;
+        if version != 3 {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let mut font = Self::empty();
+
+        while !r.0.is_empty() {
+            let tag = r.u8()?;
+            let size = r.u32()? as usize;
+            let mut block = Reader(r.bytes(size)?);
+
+            match tag {
+                // Info.
+                1 => {
+                    font.size = (block.i16()? as f32).abs();
+                    block.bytes(12)?;
+                    font.name = block.string()?;
+                }
+                // Common.
+                2 => {
+                    font.line_height = block.u16()? as f32;
+                    font.base = block.u16()? as f32;
+                    font.w = block.u16()? as u32;
+                    font.h = block.u16()? as u32;
+                }
+                // Pages.
+                3 => {
+                    while !block.0.is_empty() {
+                        font.pages.push(block.string()?);
+                    }
+                }
+                // Chars.
+                4 => {
+                    while !block.0.is_empty() {
+                        let id = block.u32()?;
+                        let (x, y) = (block.u16()? as f32, block.u16()? as f32);
+                        let (w, h) = (block.u16()? as f32, block.u16()? as f32);
+                        let x_offset = block.i16()? as f32;
+                        let y_offset = block.i16()? as f32;
+                        let advance = block.i16()? as f32;
+                        let page = block.u8()? as u32;
+                        let _channel = block.u8()?;
+
+                        font.glyphs.insert(
+                            to_char(id, "char block")?,
+                            Glyph {
+                                rect: Rect::new(x, y, x + w, y + h),
+                                x_offset,
+                                y_offset,
+                                advance,
+                                page,
+                            },
+                        );
+                    }
+                }
+                // Kerning pairs.
+                5 => {
+                    while !block.0.is_empty() {
+                        let first = to_char(block.u32()?, "kerning block")?;
+                        let second = to_char(block.u32()?, "kerning block")?;
+
+                        font.kerning.insert((first, second), block.i16()? as f32);
+                    }
+                }
+                _ => {
+                    return Err(Error::Invalid(format!("unknown block type {}", tag)));
+                }
+            }
+        }
+        Ok(font)
+    }
+
+    /// Create a texture the size of an atlas page. The page image
+    /// must be transferred to it before use.
+    pub fn texture(&self, r: &core::Renderer) -> core::Texture {
+        r.texture(self.w, self.h)
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c)
+    }
+
+    /// Return the kerning adjustment between two consecutive characters.
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.kerning.get(&(first, second)).cloned().unwrap_or(0.)
+    }
+
+    /// Lay out the given text at the given position. In the default `kit`
+    /// coordinate system, `(x, y)` is the top-left corner of the first line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::kit::text::{Font, Layout};
+    ///
+    /// let font = Font::from_text(
+    ///     "common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1\n\
+    ///      char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4\n\
+    ///      char id=97 x=0 y=0 width=6 height=8 xoffset=0 yoffset=0 xadvance=6\n",
+    /// )
+    /// .unwrap();
+    ///
+    /// // Wraps to two lines.
+    /// let glyphs = font.layout("aa aa", 0., 20., &Layout::default().wrap(14.));
+    ///
+    /// assert_eq!(glyphs.len(), 4);
+    /// assert_eq!(glyphs[2].dst.x1, 0.);
+    /// assert_eq!(glyphs[2].dst.y2, 10.);
+    /// ```
+    pub fn layout(&self, text: &str, x: f32, y: f32, layout: &Layout) -> Vec<PositionedGlyph> {
+        let mut glyphs = Vec::with_capacity(text.len());
+
+        place(self, text, x, y, layout, |index, c, pen, top| {
+            let glyph = match self.glyph(c) {
+                Some(g) => g,
+                None => return,
+            };
+            let (w, h) = (glyph.rect.width(), glyph.rect.height());
+            let x1 = pen + glyph.x_offset * layout.scale;
+            let y2 = top - glyph.y_offset * layout.scale;
+
+            if w > 0. && h > 0. {
+                glyphs.push(PositionedGlyph {
+                    c,
+                    index,
+                    page: glyph.page,
+                    src: glyph.rect,
+                    dst: Rect::new(x1, y2 - h * layout.scale, x1 + w * layout.scale, y2),
+                });
+            }
+        });
+        glyphs
+    }
+
+    fn empty() -> Self {
+        Self {
+            name: String::new(),
+            size: 0.,
+            line_height: 0.,
+            base: 0.,
+            w: 0,
+            h: 0,
+            pages: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+        }
+    }
+}
+
+impl Typeface for Font {
+    fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    fn advance(&self, c: char) -> f32 {
+        self.glyph(c).map_or(0., |g| g.advance)
+    }
+
+    fn kerning(&self, first: char, second: char) -> f32 {
+        Self::kerning(self, first, second)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Typeface
+///////////////////////////////////////////////////////////////////////////
+
+/// The font metrics needed to lay out text.
+pub trait Typeface {
+    /// Distance between two lines of text.
+    fn line_height(&self) -> f32;
+    /// How far to move the pen after drawing the given character.
+    fn advance(&self, c: char) -> f32;
+    /// Kerning adjustment between two consecutive characters.
+    fn kerning(&self, first: char, second: char) -> f32;
+}
+
+/// Lay out text with the given typeface, calling `f(index, c, pen, top)` for
+/// each character `c` at `index` in the text, where `pen` is the horizontal
+/// pen position and `top` is the top of the line the character is on.
+pub fn place<T, F>(face: &T, text: &str, x: f32, y: f32, layout: &Layout, mut f: F)
+where
+    T: Typeface,
+    F: FnMut(usize, char, f32, f32),
+{
+    let lines = lines(face, text, layout);
+    let width = layout
+        .width
+        .unwrap_or_else(|| lines.iter().map(|l| l.width).fold(0., f32::max));
+
+    for (n, line) in lines.iter().enumerate() {
+        let top = y - n as f32 * face.line_height() * layout.scale;
+        let mut pen = x + match layout.align {
+            Align::Left => 0.,
+            Align::Center => (width - line.width) / 2.,
+            Align::Right => width - line.width,
+        };
+        let mut prev: Option<char> = None;
+
+        for &(index, c) in line.chars.iter() {
+            if let Some(p) = prev {
+                pen += face.kerning(p, c) * layout.scale;
+            }
+            f(index, c, pen, top);
+
+            pen += face.advance(c) * layout.scale;
+            prev = Some(c);
+        }
+    }
+}
+
+/// Split text into lines, breaking on newlines and, if a wrap width
+/// is set, between words.
+fn lines<T: Typeface>(face: &T, text: &str, layout: &Layout) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut line = Line::default();
+    let mut word = Line::default();
+
+    let advance = |prev: Option<char>, c: char| -> f32 {
+        let kerning = prev.map_or(0., |p| face.kerning(p, c));
+        (kerning + face.advance(c)) * layout.scale
+    };
+
+    for (index, c) in text.chars().enumerate() {
+        if c == '\n' {
+            line.append(&mut word);
+            lines.push(std::mem::take(&mut line));
+            continue;
+        }
+
+        if c.is_whitespace() {
+            line.append(&mut word);
+            let a = advance(line.last(), c);
+            line.push(index, c, a);
+            continue;
+        }
+
+        let a = advance(word.last().or_else(|| line.last()), c);
+
+        if let Some(max) = layout.width {
+            if line.width + word.width + a > max && !(line.is_empty() && word.is_empty()) {
+                if line.is_empty() {
+                    // The word doesn't fit on a line by itself, break it.
+                    lines.push(std::mem::take(&mut word));
+                } else {
+                    line.trim_end();
+                    lines.push(std::mem::take(&mut line));
+                }
+            }
+        }
+        let a = advance(word.last().or_else(|| line.last()), c);
+        word.push(index, c, a);
+    }
+    line.append(&mut word);
+    lines.push(line);
+
+    lines
+}
+
+/// A line of text, as indexed characters.
+#[derive(Default, Debug)]
+struct Line {
+    chars: Vec<(usize, char)>,
+    advances: Vec<f32>,
+    width: f32,
+}
+
+impl Line {
+    fn push(&mut self, index: usize, c: char, advance: f32) {
+        self.chars.push((index, c));
+        self.advances.push(advance);
+        self.width += advance;
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        self.chars.append(&mut other.chars);
+        self.advances.append(&mut other.advances);
+        self.width += other.width;
+        other.width = 0.;
+    }
+
+    fn trim_end(&mut self) {
+        while let Some(&(_, c)) = self.chars.last() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.pop();
+            self.width -= self.advances.pop().unwrap_or(0.);
+        }
+    }
+
+    fn last(&self) -> Option<char> {
+        self.chars.last().map(|&(_, c)| c)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Layout
+///////////////////////////////////////////////////////////////////////////
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Text layout options.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Layout {
+    /// Horizontal alignment of each line.
+    pub align: Align,
+    /// Width to wrap and align text to. If `None`, text is only broken on
+    /// newlines, and aligned to the widest line.
+    pub width: Option<f32>,
+    /// Scale factor applied to the font metrics.
+    pub scale: f32,
+}
+
+impl Layout {
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn wrap(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            align: Align::Left,
+            width: None,
+            scale: 1.0,
+        }
+    }
+}
+
+/// A glyph placed by `Font::layout`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PositionedGlyph {
+    /// The character drawn.
+    pub c: char,
+    /// Index of the character in the source text.
+    pub index: usize,
+    /// Atlas page the glyph is on.
+    pub page: u32,
+    /// Region of the glyph in the atlas page.
+    pub src: Rect<f32>,
+    /// Where the glyph is drawn.
+    pub dst: Rect<f32>,
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Batch
+///////////////////////////////////////////////////////////////////////////
+
+/// A batch of text, drawn with the `sprite2d` pipeline, using a single
+/// atlas page of a font as texture.
+#[derive(Debug)]
+pub struct TextBatch<'a> {
+    font: &'a Font,
+    page: u32,
+    raw: sprite2d::Batch,
+}
+
+impl<'a> TextBatch<'a> {
+    pub fn new(font: &'a Font) -> Self {
+        Self::page(font, 0)
+    }
+
+    /// Create a batch for the given atlas page. Glyphs on other pages are skipped.
+    pub fn page(font: &'a Font, page: u32) -> Self {
+        Self {
+            font,
+            page,
+            raw: sprite2d::Batch::new(font.w, font.h),
+        }
+    }
+
+    /// Add text of a single color.
+    pub fn add(&mut self, text: &str, x: f32, y: f32, color: Rgba, layout: &Layout) {
+        self.add_with(text, x, y, layout, |_, _| color);
+    }
+
+    /// Add text, coloring each glyph with `color(index, c)`, where `index`
+    /// is the index of the character `c` in the text.
+    pub fn add_with<F>(&mut self, text: &str, x: f32, y: f32, layout: &Layout, color: F)
+    where
+        F: Fn(usize, char) -> Rgba,
+    {
+        for g in self.font.layout(text, x, y, layout) {
+            if g.page != self.page {
+                continue;
+            }
+            self.raw
+                .add(g.src, g.dst, color(g.index, g.c), 1.0, Repeat::default());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.raw.clear();
+    }
+
+    pub fn vertices(&self) -> Vec<sprite2d::Vertex> {
+        self.raw.vertices()
+    }
+
+    pub fn finish(self, r: &core::Renderer) -> core::VertexBuffer<sprite2d::Vertex> {
+        self.raw.finish(r)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Parsing
+///////////////////////////////////////////////////////////////////////////
+
+fn parse<T: FromStr>(s: &str, context: &str) -> Result<T, Error> {
+    s.parse()
+        .map_err(|_| Error::Invalid(format!("expected number, got `{}` in `{}`", s, context)))
+}
+
+fn to_char(id: u32, context: &str) -> Result<char, Error> {
+    std::char::from_u32(id)
+        .ok_or_else(|| Error::Invalid(format!("invalid character {} in `{}`", id, context)))
+}
+
+/// The attributes of a text format line.
+struct Attrs<'a> {
+    line: &'a str,
+    map: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Attrs<'a> {
+    fn get(&self, key: &str) -> Result<&'a str, Error> {
+        self.map
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::Invalid(format!("missing `{}` in `{}`", key, self.line)))
+    }
+
+    fn num(&self, key: &str) -> Result<i32, Error> {
+        parse(self.get(key)?, self.line)
+    }
+
+    fn unsigned(&self, key: &str) -> Result<u32, Error> {
+        let n = self.num(key)?;
+        u32::try_from(n).map_err(|_| {
+            Error::Invalid(format!(
+                "expected non-negative `{}`, got {} in `{}`",
+                key, n, self.line
+            ))
+        })
+    }
+}
+
+/// Iterator over the `key=value` pairs of a text format line. Values may
+/// be quoted, and the leading tag has no value.
+struct Tokens<'a>(&'a str);
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str) -> Self {
+        Tokens(line)
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (&'a str, Option<&'a str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = self.0.trim_start();
+        if s.is_empty() {
+            return None;
+        }
+        let end = s
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(s.len());
+        let (key, rest) = s.split_at(end);
+
+        if !rest.starts_with('=') {
+            self.0 = rest;
+            return Some((key, None));
+        }
+        let rest = &rest[1..];
+
+        let (value, rest) = if let Some(rest) = rest.strip_prefix('"') {
+            let end = rest.find('"').unwrap_or(rest.len());
+            (&rest[..end], rest.get(end + 1..).unwrap_or(""))
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            rest.split_at(end)
+        };
+        self.0 = rest;
+
+        Some((key, Some(value)))
+    }
+}
+
+/// Little-endian reader over binary font data.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < n {
+            return Err(Error::UnexpectedEof);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let end = self
+            .0
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(Error::UnexpectedEof)?;
+        let s = String::from_utf8_lossy(&self.0[..end]).into_owned();
+        self.0 = &self.0[end + 1..];
+
+        Ok(s)
+    }
+}
@@ -0,0 +1,438 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::collections::HashMap;
+use std::time;
+
+use crate::core;
+use crate::core::{Rect, Rgba};
+
+use crate::kit::sprite2d;
+use crate::kit::{Animation, Repeat};
+
+/// An index into a `Tileset`, counting tiles left-to-right, top-to-bottom.
+pub type TileId = u32;
+
+///////////////////////////////////////////////////////////////////////////
+// Tileset
+///////////////////////////////////////////////////////////////////////////
+
+/// A texture atlas divided into equally sized tiles.
+#[derive(Clone, Debug)]
+pub struct Tileset {
+    /// Width of the atlas texture, in pixels.
+    pub w: u32,
+    /// Height of the atlas texture, in pixels.
+    pub h: u32,
+    /// Width of a single tile, in pixels.
+    pub tile_w: u32,
+    /// Height of a single tile, in pixels.
+    pub tile_h: u32,
+    /// Space around the edge of the atlas, in pixels.
+    pub margin: u32,
+    /// Space between adjacent tiles, in pixels.
+    pub spacing: u32,
+
+    animations: HashMap<TileId, Animation<TileId>>,
+}
+
+impl Tileset {
+    pub fn new(w: u32, h: u32, tile_w: u32, tile_h: u32) -> Self {
+        Self {
+            w,
+            h,
+            tile_w,
+            tile_h,
+            margin: 0,
+            spacing: 0,
+            animations: HashMap::new(),
+        }
+    }
+
+    /// Number of tile columns in the atlas.
+    pub fn columns(&self) -> u32 {
+        self.fit(self.w, self.tile_w)
+    }
+
+    /// Number of tile rows in the atlas.
+    pub fn rows(&self) -> u32 {
+        self.fit(self.h, self.tile_h)
+    }
+
+    /// Total number of tiles in the atlas.
+    pub fn len(&self) -> usize {
+        (self.columns() * self.rows()) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the texture region of the given tile, in pixels. An atlas with
+    /// no columns has no tiles, and returns an empty region.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rgx::core::Rect;
+    /// use rgx::kit::tilemap::Tileset;
+    ///
+    /// let tileset = Tileset::new(64, 32, 16, 16);
+    /// assert_eq!(tileset.region(5), Rect::new(16., 16., 32., 32.));
+    /// ```
+    pub fn region(&self, id: TileId) -> Rect<f32> {
+        let cols = self.columns();
+        if cols == 0 {
+            return Rect::new(0., 0., 0., 0.);
+        }
+        let (col, row) = (id % cols, id / cols);
+
+        let x = self.margin + col * (self.tile_w + self.spacing);
+        let y = self.margin + row * (self.tile_h + self.spacing);
+
+        Rect::new(
+            x as f32,
+            y as f32,
+            (x + self.tile_w) as f32,
+            (y + self.tile_h) as f32,
+        )
+    }
+
+    /// Number of tiles of the given size that fit along an edge of the
+    /// given length. Margins wider than the atlas, and zero-sized tiles
+    /// without spacing, fit no tiles.
+    fn fit(&self, len: u32, tile: u32) -> u32 {
+        let step = tile + self.spacing;
+        if step == 0 {
+            return 0;
+        }
+        (len.saturating_sub(self.margin * 2) + self.spacing) / step
+    }
+
+    /// Animate the given tile. Wherever `id` is placed on a map, the current
+    /// frame of the animation is displayed instead.
+    pub fn animate(&mut self, id: TileId, anim: Animation<TileId>) {
+        self.animations.insert(id, anim);
+    }
+
+    /// Check whether the given tile is animated.
+    pub fn is_animated(&self, id: TileId) -> bool {
+        self.animations.contains_key(&id)
+    }
+
+    /// Return the tile to display in place of `id`.
+    pub fn resolve(&self, id: TileId) -> TileId {
+        match self.animations.get(&id) {
+            Some(anim) if !anim.is_empty() => anim.val(),
+            _ => id,
+        }
+    }
+
+    /// Step all tile animations, returning the tiles whose frame changed.
+    pub fn step(&mut self, delta: time::Duration) -> Vec<TileId> {
+        let mut changed = Vec::new();
+
+        for (id, anim) in self.animations.iter_mut() {
+            if anim.is_empty() {
+                continue;
+            }
+            let cursor = anim.cursor();
+            anim.step(delta);
+
+            if anim.cursor() != cursor {
+                changed.push(*id);
+            }
+        }
+        changed
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Layer
+///////////////////////////////////////////////////////////////////////////
+
+/// A section of a layer whose tiles share a single vertex buffer.
+struct Chunk {
+    /// Bounds of the chunk, in map space.
+    rect: Rect<f32>,
+    /// Vertex buffer, or `None` if the chunk has no tiles.
+    buffer: Option<core::VertexBuffer<sprite2d::Vertex>>,
+    /// Whether the buffer needs to be rebuilt.
+    dirty: bool,
+    /// Animated tiles placed in this chunk, as of the last rebuild.
+    animated: Vec<TileId>,
+}
+
+/// A grid of tiles, drawn on top of the layers before it.
+pub struct Layer {
+    tiles: Vec<Option<TileId>>,
+    chunks: Vec<Chunk>,
+    visible: bool,
+    opacity: f32,
+}
+
+impl Layer {
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    fn invalidate(&mut self) {
+        for c in self.chunks.iter_mut() {
+            c.dirty = true;
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Tilemap
+///////////////////////////////////////////////////////////////////////////
+
+/// A layered, chunked grid of tiles referencing a single `Tileset`.
+///
+/// Each chunk of `chunk_size` by `chunk_size` tiles is uploaded to its own
+/// vertex buffer, which is only rebuilt when one of its tiles changes.
+/// Tiles are laid out from the map origin, each the size of a tileset tile;
+/// use a transform to position or scale the map.
+pub struct Tilemap {
+    pub tileset: Tileset,
+
+    /// Width of the map, in tiles.
+    pub w: u32,
+    /// Height of the map, in tiles.
+    pub h: u32,
+    /// Width and height of a chunk, in tiles.
+    pub chunk_size: u32,
+
+    layers: Vec<Layer>,
+}
+
+impl Tilemap {
+    pub const DEFAULT_CHUNK_SIZE: u32 = 16;
+
+    pub fn new(tileset: Tileset, w: u32, h: u32) -> Self {
+        Self::with_chunk_size(tileset, w, h, Self::DEFAULT_CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(tileset: Tileset, w: u32, h: u32, chunk_size: u32) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than zero");
+
+        Self {
+            tileset,
+            w,
+            h,
+            chunk_size,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Return the bounds of the map, in map space.
+    pub fn rect(&self) -> Rect<f32> {
+        Rect::origin(
+            (self.w * self.tileset.tile_w) as f32,
+            (self.h * self.tileset.tile_h) as f32,
+        )
+    }
+
+    /// Add an empty layer on top of the existing ones, returning its index.
+    pub fn add_layer(&mut self) -> usize {
+        let (cols, rows) = self.chunk_dims();
+        let (cw, ch) = (
+            (self.chunk_size * self.tileset.tile_w) as f32,
+            (self.chunk_size * self.tileset.tile_h) as f32,
+        );
+
+        let mut chunks = Vec::with_capacity((cols * rows) as usize);
+        for row in 0..rows {
+            for col in 0..cols {
+                let (x, y) = (col as f32 * cw, row as f32 * ch);
+
+                chunks.push(Chunk {
+                    rect: Rect::new(x, y, x + cw, y + ch),
+                    buffer: None,
+                    dirty: false,
+                    animated: Vec::new(),
+                });
+            }
+        }
+
+        self.layers.push(Layer {
+            tiles: vec![None; (self.w * self.h) as usize],
+            chunks,
+            visible: true,
+            opacity: 1.0,
+        });
+        self.layers.len() - 1
+    }
+
+    pub fn layer(&self, layer: usize) -> &Layer {
+        &self.layers[layer]
+    }
+
+    pub fn layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn set_visible(&mut self, layer: usize, visible: bool) {
+        self.layers[layer].visible = visible;
+    }
+
+    pub fn set_opacity(&mut self, layer: usize, opacity: f32) {
+        let l = &mut self.layers[layer];
+
+        if (l.opacity - opacity).abs() > f32::EPSILON {
+            l.opacity = opacity;
+            l.invalidate();
+        }
+    }
+
+    /// Get the tile at the given position.
+    pub fn get(&self, layer: usize, x: u32, y: u32) -> Option<TileId> {
+        self.layers[layer].tiles[self.index(x, y)]
+    }
+
+    /// Set the tile at the given position, marking its chunk for rebuild.
+    pub fn set(&mut self, layer: usize, x: u32, y: u32, tile: Option<TileId>) {
+        let i = self.index(x, y);
+        let c = self.chunk_index(x, y);
+        let l = &mut self.layers[layer];
+
+        if l.tiles[i] != tile {
+            l.tiles[i] = tile;
+            l.chunks[c].dirty = true;
+        }
+    }
+
+    /// Set every tile of the given layer.
+    pub fn fill(&mut self, layer: usize, tile: Option<TileId>) {
+        let l = &mut self.layers[layer];
+
+        for t in l.tiles.iter_mut() {
+            *t = tile;
+        }
+        l.invalidate();
+    }
+
+    /// Step tile animations, marking chunks containing changed tiles for rebuild.
+    pub fn step(&mut self, delta: time::Duration) {
+        let changed = self.tileset.step(delta);
+
+        if changed.is_empty() {
+            return;
+        }
+        for l in self.layers.iter_mut() {
+            for c in l.chunks.iter_mut() {
+                if c.animated.iter().any(|id| changed.contains(id)) {
+                    c.dirty = true;
+                }
+            }
+        }
+    }
+
+    /// Rebuild the vertex buffers of all chunks that have changed.
+    pub fn prepare(&mut self, r: &core::Renderer) {
+        let (cols, _) = self.chunk_dims();
+
+        for l in self.layers.iter_mut() {
+            for (i, c) in l.chunks.iter_mut().enumerate() {
+                if !c.dirty {
+                    continue;
+                }
+                let (cx, cy) = (i as u32 % cols, i as u32 / cols);
+                let (x1, y1) = (cx * self.chunk_size, cy * self.chunk_size);
+                let (x2, y2) = (
+                    u32::min(x1 + self.chunk_size, self.w),
+                    u32::min(y1 + self.chunk_size, self.h),
+                );
+                let (tw, th) = (self.tileset.tile_w as f32, self.tileset.tile_h as f32);
+
+                let mut batch = sprite2d::Batch::new(self.tileset.w, self.tileset.h);
+                c.animated.clear();
+
+                for y in y1..y2 {
+                    for x in x1..x2 {
+                        if let Some(id) = l.tiles[(y * self.w + x) as usize] {
+                            let (px, py) = (x as f32 * tw, y as f32 * th);
+
+                            batch.add(
+                                self.tileset.region(self.tileset.resolve(id)),
+                                Rect::new(px, py, px + tw, py + th),
+                                Rgba::TRANSPARENT,
+                                l.opacity,
+                                Repeat::default(),
+                            );
+                            if self.tileset.is_animated(id) && !c.animated.contains(&id) {
+                                c.animated.push(id);
+                            }
+                        }
+                    }
+                }
+
+                c.buffer = if batch.size > 0 {
+                    Some(batch.finish(r))
+                } else {
+                    None
+                };
+                c.dirty = false;
+            }
+        }
+    }
+
+    /// Return the vertex buffers of the visible layers which intersect `view`,
+    /// from the bottom layer to the top. `view` is given in map space.
+    pub fn visible(&self, view: Rect<f32>) -> Vec<&core::VertexBuffer<sprite2d::Vertex>> {
+        let view = Rect::new(
+            f32::min(view.x1, view.x2),
+            f32::min(view.y1, view.y2),
+            f32::max(view.x1, view.x2),
+            f32::max(view.y1, view.y2),
+        );
+
+        self.layers
+            .iter()
+            .filter(|l| l.visible)
+            .flat_map(|l| l.chunks.iter())
+            .filter(|c| c.rect.intersects(view))
+            .filter_map(|c| c.buffer.as_ref())
+            .collect()
+    }
+
+    /// Draw the chunks intersecting `view` with a `sprite2d` pipeline, using a
+    /// `sprite2d` texture binding for the tileset.
+    pub fn draw(
+        &self,
+        view: Rect<f32>,
+        binding: &core::BindingGroup,
+        pass: &mut core::PipelinePass<sprite2d::Vertex>,
+    ) {
+        for buf in self.visible(view) {
+            pass.draw(buf, binding);
+        }
+    }
+
+    fn chunk_dims(&self) -> (u32, u32) {
+        (
+            (self.w + self.chunk_size - 1) / self.chunk_size,
+            (self.h + self.chunk_size - 1) / self.chunk_size,
+        )
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.w && y < self.h,
+            "tile position ({}, {}) is outside of the {}x{} map",
+            x,
+            y,
+            self.w,
+            self.h
+        );
+        (y * self.w + x) as usize
+    }
+
+    fn chunk_index(&self, x: u32, y: u32) -> usize {
+        let (cols, _) = self.chunk_dims();
+        ((y / self.chunk_size) * cols + x / self.chunk_size) as usize
+    }
+}
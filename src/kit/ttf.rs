@@ -0,0 +1,503 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rusttype as rt;
+
+use crate::core;
+use crate::core::{Op, Rect, Rgba, Rgba8};
+
+use crate::kit::sprite2d;
+use crate::kit::text::{self, Error, Layout, PositionedGlyph, Typeface};
+use crate::kit::Repeat;
+
+/// Counter used to give each loaded font a unique identifier.
+static FONT_ID: AtomicUsize = AtomicUsize::new(0);
+
+///////////////////////////////////////////////////////////////////////////
+// Font
+///////////////////////////////////////////////////////////////////////////
+
+/// A scalable TrueType or OpenType font.
+#[derive(Clone)]
+pub struct Font {
+    id: usize,
+    inner: rt::Font<'static>,
+}
+
+impl Font {
+    /// Load a font from the contents of a TTF or OTF file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Self::new(rt::Font::from_bytes(bytes))
+    }
+
+    /// Load a font from static data, eg. from `include_bytes!`.
+    pub fn from_static(bytes: &'static [u8]) -> Result<Self, Error> {
+        Self::new(rt::Font::from_bytes(bytes))
+    }
+
+    /// Return this font at the given pixel size.
+    pub fn face(&self, px: f32) -> Face<'_> {
+        // Sizes are quantized to a quarter pixel, so that glyphs can be shared
+        // in the cache between sizes that would rasterize identically.
+        let size = (px * 4.).round() as u32;
+
+        Face {
+            font: self,
+            size,
+            scale: rt::Scale::uniform(size as f32 / 4.),
+        }
+    }
+
+    fn new(result: Result<rt::Font<'static>, rt::Error>) -> Result<Self, Error> {
+        let inner = result.map_err(|e| Error::Invalid(e.to_string()))?;
+
+        Ok(Self {
+            id: FONT_ID.fetch_add(1, Ordering::Relaxed),
+            inner,
+        })
+    }
+}
+
+/// A font at a given pixel size.
+#[derive(Clone)]
+pub struct Face<'a> {
+    font: &'a Font,
+    size: u32,
+    scale: rt::Scale,
+}
+
+impl<'a> Face<'a> {
+    /// The pixel size of the face.
+    pub fn px(&self) -> f32 {
+        self.scale.y
+    }
+
+    /// Distance from the top of a line to the baseline.
+    pub fn ascent(&self) -> f32 {
+        self.font.inner.v_metrics(self.scale).ascent
+    }
+
+    fn glyph(&self, c: char) -> rt::ScaledGlyph<'static> {
+        self.font.inner.glyph(c).scaled(self.scale)
+    }
+}
+
+impl<'a> Typeface for Face<'a> {
+    fn line_height(&self) -> f32 {
+        let v = self.font.inner.v_metrics(self.scale);
+        v.ascent - v.descent + v.line_gap
+    }
+
+    fn advance(&self, c: char) -> f32 {
+        self.glyph(c).h_metrics().advance_width
+    }
+
+    fn kerning(&self, first: char, second: char) -> f32 {
+        self.font.inner.pair_kerning(self.scale, first, second)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// GlyphCache
+///////////////////////////////////////////////////////////////////////////
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+struct GlyphKey {
+    font: usize,
+    glyph: rt::GlyphId,
+    size: u32,
+}
+
+/// A row of glyphs in the cache. Glyphs are only ever evicted a row at a time.
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    h: u32,
+    /// Horizontal position of the next glyph.
+    cursor: u32,
+    /// Tick at which a glyph of this shelf was last used.
+    last_used: u64,
+    glyphs: Vec<GlyphKey>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    rect: Rect<u32>,
+    shelf: usize,
+}
+
+/// A texture atlas of glyphs, rasterized on the CPU as they are laid out.
+///
+/// The cache starts out at the given size, and doubles in size whenever it
+/// runs out of space, up to a maximum. Past that, the least recently used
+/// glyphs are evicted to make room. Glyphs laid out since the last call to
+/// `GlyphCache::prepare` are never evicted, so all text of a frame should be
+/// laid out before the cache is prepared, and batches finished after it is.
+pub struct GlyphCache {
+    w: u32,
+    h: u32,
+    max: u32,
+
+    pixels: Vec<Rgba8>,
+    shelves: Vec<Shelf>,
+    glyphs: HashMap<GlyphKey, Entry>,
+
+    texture: Option<core::Texture>,
+    dirty: Vec<Rect<u32>>,
+    resized: bool,
+    tick: u64,
+}
+
+impl GlyphCache {
+    /// Space left between glyphs, to prevent bleeding when sampling.
+    const PADDING: u32 = 1;
+
+    pub fn new(size: u32, max: u32) -> Self {
+        assert!(size > 0, "initial cache size must be > 0");
+        assert!(size <= max, "initial cache size must be <= maximum size");
+
+        Self {
+            w: size,
+            h: size,
+            max,
+            pixels: vec![Rgba8::TRANSPARENT; size as usize * size as usize],
+            shelves: Vec::new(),
+            glyphs: HashMap::new(),
+            texture: None,
+            dirty: Vec::new(),
+            resized: false,
+            tick: 0,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.h
+    }
+
+    /// The cache texture, once the cache has been prepared.
+    pub fn texture(&self) -> Option<&core::Texture> {
+        self.texture.as_ref()
+    }
+
+    /// Lay out text, rasterizing any glyphs that aren't yet cached. The
+    /// returned glyphs' `src` rectangles refer to the cache texture.
+    pub fn layout(
+        &mut self,
+        face: &Face,
+        text: &str,
+        x: f32,
+        y: f32,
+        layout: &Layout,
+    ) -> Vec<PositionedGlyph> {
+        // Rasterize at the final size rather than scaling glyphs up or down.
+        let face = face.font.face(face.px() * layout.scale);
+        let layout = Layout {
+            scale: 1.0,
+            ..layout.clone()
+        };
+        let ascent = face.ascent();
+        let mut glyphs = Vec::with_capacity(text.len());
+
+        text::place(&face, text, x, y, &layout, |index, c, pen, top| {
+            let glyph = face.glyph(c).positioned(rt::point(0., 0.));
+            let bb = match glyph.pixel_bounding_box() {
+                Some(bb) => bb,
+                None => return,
+            };
+            let key = GlyphKey {
+                font: face.font.id,
+                glyph: glyph.id(),
+                size: face.size,
+            };
+            let src = match self.cache(key, &glyph) {
+                Some(rect) => rect,
+                None => return,
+            };
+            let (w, h) = (bb.width() as f32, bb.height() as f32);
+            let x1 = (pen + bb.min.x as f32).round();
+            let y2 = (top - ascent - bb.min.y as f32).round();
+
+            glyphs.push(PositionedGlyph {
+                c,
+                index,
+                page: 0,
+                src: src.map(|n| n as f32),
+                dst: Rect::new(x1, y2 - h, x1 + w, y2),
+            });
+        });
+        glyphs
+    }
+
+    /// Upload newly rasterized glyphs to the cache texture. Returns `true` if
+    /// the texture was (re)created, in which case any binding group using it
+    /// must be recreated as well.
+    pub fn prepare(&mut self, r: &mut core::Renderer) -> bool {
+        self.tick += 1;
+
+        match self.texture {
+            Some(ref texture) if !self.resized => {
+                let dirty = std::mem::take(&mut self.dirty);
+                let regions: Vec<(Vec<Rgba8>, Rect<u32>)> =
+                    dirty.into_iter().map(|d| (self.region(d), d)).collect();
+
+                let ops: Vec<Op<Rgba8>> = regions
+                    .iter()
+                    .map(|(buf, d)| {
+                        // Transfers are specified with the Y axis pointing up.
+                        Op::Transfer(
+                            texture,
+                            buf.as_slice(),
+                            d.width(),
+                            d.height(),
+                            Rect::new(d.x1, self.h - d.y2, d.x2, self.h - d.y1).map(|n| n as i32),
+                        )
+                    })
+                    .collect();
+
+                if !ops.is_empty() {
+                    r.prepare(&ops);
+                }
+                false
+            }
+            _ => {
+                let texture = r.texture(self.w, self.h);
+                r.prepare(&[Op::Fill(&texture, self.pixels.as_slice())]);
+
+                self.texture = Some(texture);
+                self.dirty.clear();
+                self.resized = false;
+
+                true
+            }
+        }
+    }
+
+    /// Return the cache region of the given glyph, rasterizing it if needed.
+    fn cache(&mut self, key: GlyphKey, glyph: &rt::PositionedGlyph) -> Option<Rect<u32>> {
+        if let Some(entry) = self.glyphs.get(&key) {
+            self.shelves[entry.shelf].last_used = self.tick;
+            return Some(entry.rect);
+        }
+        let bb = glyph.pixel_bounding_box()?;
+        let (w, h) = (bb.width() as u32, bb.height() as u32);
+        let (shelf, rect) = self.allocate(w, h)?;
+
+        let (x, y) = (rect.x1, rect.y1);
+        let stride = self.w;
+        let pixels = &mut self.pixels;
+
+        glyph.draw(|gx, gy, v| {
+            let i = (y + gy) as usize * stride as usize + (x + gx) as usize;
+            pixels[i] = Rgba8::new(0xff, 0xff, 0xff, (v * 255.).round() as u8);
+        });
+
+        let s = &mut self.shelves[shelf];
+        s.glyphs.push(key);
+        s.last_used = self.tick;
+
+        self.glyphs.insert(key, Entry { rect, shelf });
+        self.dirty.push(rect);
+
+        Some(rect)
+    }
+
+    /// Find room for a glyph of the given size, growing the cache or
+    /// evicting glyphs if necessary.
+    fn allocate(&mut self, w: u32, h: u32) -> Option<(usize, Rect<u32>)> {
+        let (pw, ph) = (w + Self::PADDING, h + Self::PADDING);
+
+        if pw > self.max || ph > self.max {
+            return None;
+        }
+
+        loop {
+            // Prefer an existing shelf of a similar height.
+            if let Some(i) = self.find_shelf(pw, ph, ph + ph / 4) {
+                return Some((i, self.place(i, w, h)));
+            }
+
+            // Then, start a new shelf below the existing ones.
+            let top = self.shelves.iter().map(|s| s.y + s.h).max().unwrap_or(0);
+            if top + ph <= self.h {
+                self.shelves.push(Shelf {
+                    y: top,
+                    h: u32::min(ph, self.h - top),
+                    cursor: 0,
+                    last_used: self.tick,
+                    glyphs: Vec::new(),
+                });
+                let i = self.shelves.len() - 1;
+
+                return Some((i, self.place(i, w, h)));
+            }
+
+            // Then, grow the cache and try again.
+            if self.w < self.max || self.h < self.max {
+                self.grow();
+                continue;
+            }
+
+            // Then, settle for any shelf tall enough.
+            if let Some(i) = self.find_shelf(pw, ph, u32::MAX) {
+                return Some((i, self.place(i, w, h)));
+            }
+
+            // Finally, evict the least recently used shelf that's tall enough.
+            let tick = self.tick;
+            let lru = self
+                .shelves
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.h >= ph && s.last_used < tick)
+                .min_by_key(|(_, s)| s.last_used)
+                .map(|(i, _)| i)?;
+
+            self.evict(lru);
+            return Some((lru, self.place(lru, w, h)));
+        }
+    }
+
+    fn find_shelf(&self, w: u32, h: u32, max_h: u32) -> Option<usize> {
+        self.shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.h >= h && s.h <= max_h && s.cursor + w <= self.w)
+            .min_by_key(|(_, s)| s.h)
+            .map(|(i, _)| i)
+    }
+
+    fn place(&mut self, shelf: usize, w: u32, h: u32) -> Rect<u32> {
+        let s = &mut self.shelves[shelf];
+        let rect = Rect::new(s.cursor, s.y, s.cursor + w, s.y + h);
+
+        s.cursor += w + Self::PADDING;
+        rect
+    }
+
+    fn evict(&mut self, shelf: usize) {
+        let s = &mut self.shelves[shelf];
+
+        for key in s.glyphs.drain(..) {
+            self.glyphs.remove(&key);
+        }
+        s.cursor = 0;
+
+        let (y1, y2) = (s.y, s.y + s.h);
+        let w = self.w as usize;
+
+        for p in self.pixels[y1 as usize * w..y2 as usize * w].iter_mut() {
+            *p = Rgba8::TRANSPARENT;
+        }
+        self.dirty.push(Rect::new(0, y1, self.w, y2));
+    }
+
+    /// Double the size of the cache, keeping existing glyphs in place.
+    fn grow(&mut self) {
+        let (w, h) = (
+            u32::min(self.w.saturating_mul(2), self.max),
+            u32::min(self.h.saturating_mul(2), self.max),
+        );
+        let mut pixels = vec![Rgba8::TRANSPARENT; w as usize * h as usize];
+
+        for (row, src) in self.pixels.chunks(self.w as usize).enumerate() {
+            let start = row * w as usize;
+            pixels[start..start + src.len()].copy_from_slice(src);
+        }
+        self.pixels = pixels;
+        self.w = w;
+        self.h = h;
+        self.resized = true;
+    }
+
+    /// Copy a region of the cache into a new buffer.
+    fn region(&self, rect: Rect<u32>) -> Vec<Rgba8> {
+        let mut buf = Vec::with_capacity(rect.width() as usize * rect.height() as usize);
+
+        for y in rect.y1..rect.y2 {
+            let start = y as usize * self.w as usize + rect.x1 as usize;
+            buf.extend_from_slice(&self.pixels[start..start + rect.width() as usize]);
+        }
+        buf
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Batch
+///////////////////////////////////////////////////////////////////////////
+
+/// A batch of text, drawn with the `sprite2d` pipeline, using a
+/// `GlyphCache` texture.
+#[derive(Debug, Default)]
+pub struct TextBatch {
+    items: Vec<(PositionedGlyph, Rgba)>,
+}
+
+impl TextBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add text of a single color.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add(
+        &mut self,
+        cache: &mut GlyphCache,
+        face: &Face,
+        text: &str,
+        x: f32,
+        y: f32,
+        color: Rgba,
+        layout: &Layout,
+    ) {
+        self.add_with(cache, face, text, x, y, layout, |_, _| color);
+    }
+
+    /// Add text, coloring each glyph with `color(index, c)`, where `index`
+    /// is the index of the character `c` in the text.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_with<F>(
+        &mut self,
+        cache: &mut GlyphCache,
+        face: &Face,
+        text: &str,
+        x: f32,
+        y: f32,
+        layout: &Layout,
+        color: F,
+    ) where
+        F: Fn(usize, char) -> Rgba,
+    {
+        for g in cache.layout(face, text, x, y, layout) {
+            self.items.push((g, color(g.index, g.c)));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Build the vertex buffer for this batch. The cache must have been
+    /// prepared since the text was added.
+    pub fn finish(
+        self,
+        cache: &GlyphCache,
+        r: &core::Renderer,
+    ) -> core::VertexBuffer<sprite2d::Vertex> {
+        let mut batch = sprite2d::Batch::new(cache.width(), cache.height());
+
+        for (g, color) in self.items {
+            batch.add(g.src, g.dst, color, 1.0, Repeat::default());
+        }
+        batch.finish(r)
+    }
+}
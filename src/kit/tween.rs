@@ -0,0 +1,507 @@
+#![deny(clippy::all, clippy::use_self)]
+
+use std::f32::consts::PI;
+use std::time;
+
+use crate::core::{Rect, Rgba, Rgba8};
+use crate::math::{Matrix4, Point2, Vector2, Vector3, Vector4};
+
+///////////////////////////////////////////////////////////////////////////
+// Interpolate
+///////////////////////////////////////////////////////////////////////////
+
+/// Types which can be linearly interpolated.
+pub trait Interpolate {
+    /// Interpolate between `self` and `other`, where `t` is usually in the
+    /// range `0..1`. Values outside of that range extrapolate, which some
+    /// easing curves rely on to overshoot.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for f64 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * Self::from(t)
+    }
+}
+
+impl Interpolate for Vector2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(self.x.lerp(&other.x, t), self.y.lerp(&other.y, t))
+    }
+}
+
+impl Interpolate for Vector3<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.x.lerp(&other.x, t),
+            self.y.lerp(&other.y, t),
+            self.z.lerp(&other.z, t),
+        )
+    }
+}
+
+impl Interpolate for Vector4<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.x.lerp(&other.x, t),
+            self.y.lerp(&other.y, t),
+            self.z.lerp(&other.z, t),
+            self.w.lerp(&other.w, t),
+        )
+    }
+}
+
+impl Interpolate for Point2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(self.x.lerp(&other.x, t), self.y.lerp(&other.y, t))
+    }
+}
+
+/// Transforms are interpolated component-wise, which is only accurate for
+/// translations and scaling.
+impl Interpolate for Matrix4<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            x: self.x.lerp(&other.x, t),
+            y: self.y.lerp(&other.y, t),
+            z: self.z.lerp(&other.z, t),
+            w: self.w.lerp(&other.w, t),
+        }
+    }
+}
+
+impl Interpolate for Rgba {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.r.lerp(&other.r, t),
+            self.g.lerp(&other.g, t),
+            self.b.lerp(&other.b, t),
+            self.a.lerp(&other.a, t),
+        )
+    }
+}
+
+impl Interpolate for Rgba8 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Rgba::from(*self).lerp(&Rgba::from(*other), t).into()
+    }
+}
+
+impl Interpolate for Rect<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.x1.lerp(&other.x1, t),
+            self.y1.lerp(&other.y1, t),
+            self.x2.lerp(&other.x2, t),
+            self.y2.lerp(&other.y2, t),
+        )
+    }
+}
+
+impl<A: Interpolate, B: Interpolate> Interpolate for (A, B) {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        (self.0.lerp(&other.0, t), self.1.lerp(&other.1, t))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Easing
+///////////////////////////////////////////////////////////////////////////
+
+/// An easing curve, defined by how it accelerates away from its start.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Curve {
+    Linear,
+    Quad,
+    Cubic,
+    Quart,
+    Quint,
+    Sine,
+    Expo,
+    Circ,
+    /// Pulls back slightly before moving forward.
+    Back,
+    /// Oscillates like a spring.
+    Elastic,
+    /// Bounces like a falling ball, in reverse.
+    Bounce,
+}
+
+impl Curve {
+    /// Apply the "in" variant of the curve to `t`, in the range `0..1`.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::Quad => t.powi(2),
+            Self::Cubic => t.powi(3),
+            Self::Quart => t.powi(4),
+            Self::Quint => t.powi(5),
+            Self::Sine => 1. - (t * PI / 2.).cos(),
+            Self::Expo if t <= 0. => 0.,
+            Self::Expo => 2f32.powf(10. * t - 10.),
+            Self::Circ => 1. - (1. - t * t).max(0.).sqrt(),
+            Self::Back => {
+                let c = 1.70158;
+                (c + 1.) * t.powi(3) - c * t.powi(2)
+            }
+            Self::Elastic if t <= 0. || t >= 1. => t,
+            Self::Elastic => {
+                -(2f32.powf(10. * t - 10.)) * ((10. * t - 10.75) * (2. * PI / 3.)).sin()
+            }
+            Self::Bounce => 1. - bounce(1. - t),
+        }
+    }
+}
+
+/// The bounce curve, ending with a series of decreasing bounces.
+fn bounce(t: f32) -> f32 {
+    let (n, d) = (7.5625, 2.75);
+
+    if t < 1. / d {
+        n * t * t
+    } else if t < 2. / d {
+        let t = t - 1.5 / d;
+        n * t * t + 0.75
+    } else if t < 2.5 / d {
+        let t = t - 2.25 / d;
+        n * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d;
+        n * t * t + 0.984_375
+    }
+}
+
+/// An easing function, mapping linear progress to eased progress.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::kit::tween::{Curve, Ease};
+///
+/// for ease in &[Ease::In(Curve::Quad), Ease::Out(Curve::Bounce), Ease::InOut(Curve::Elastic)] {
+///     assert!(ease.apply(0.).abs() < 1e-6);
+///     assert!((ease.apply(1.) - 1.).abs() < 1e-6);
+/// }
+/// assert_eq!(Ease::In(Curve::Quad).apply(0.5), 0.25);
+/// assert_eq!(Ease::Out(Curve::Quad).apply(0.5), 0.75);
+/// assert_eq!(Ease::InOut(Curve::Cubic).apply(0.5), 0.5);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub enum Ease {
+    /// Accelerate away from the start.
+    In(Curve),
+    /// Decelerate into the end.
+    Out(Curve),
+    /// Accelerate, then decelerate.
+    InOut(Curve),
+    /// A custom easing function.
+    Custom(fn(f32) -> f32),
+}
+
+impl Ease {
+    pub const LINEAR: Self = Self::In(Curve::Linear);
+
+    /// Apply the easing function to `t`, which is clamped to `0..1`.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+
+        match self {
+            Self::In(c) => c.apply(t),
+            Self::Out(c) => 1. - c.apply(1. - t),
+            Self::InOut(c) if t < 0.5 => c.apply(t * 2.) / 2.,
+            Self::InOut(c) => 1. - c.apply(2. - t * 2.) / 2.,
+            Self::Custom(f) => f(t),
+        }
+    }
+}
+
+impl Default for Ease {
+    fn default() -> Self {
+        Self::LINEAR
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Timeline
+///////////////////////////////////////////////////////////////////////////
+
+/// Something that plays out over a fixed duration.
+pub trait Timeline {
+    /// Total duration of the timeline.
+    fn duration(&self) -> time::Duration;
+
+    /// Time elapsed since the start of the timeline.
+    fn elapsed(&self) -> time::Duration;
+
+    /// Jump to the given time, clamped to the duration of the timeline.
+    fn seek(&mut self, time: time::Duration);
+
+    /// Advance the timeline by `delta`, like `Animation::step`. Returns the
+    /// part of `delta` left over once the timeline has finished.
+    fn step(&mut self, delta: time::Duration) -> time::Duration {
+        let target = self.elapsed() + delta;
+        let duration = self.duration();
+
+        self.seek(target);
+
+        if target > duration {
+            target - duration
+        } else {
+            time::Duration::new(0, 0)
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed() >= self.duration()
+    }
+
+    /// Restart the timeline from the beginning.
+    fn reset(&mut self) {
+        self.seek(time::Duration::new(0, 0));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Tween
+///////////////////////////////////////////////////////////////////////////
+
+/// A value transitioning between two others.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::Rgba;
+/// use rgx::kit::tween::{Curve, Ease, Timeline, Tween};
+/// use std::time::Duration;
+///
+/// let mut fade = Tween::new(Rgba::BLACK, Rgba::WHITE, Duration::from_millis(200))
+///     .ease(Ease::Out(Curve::Quad));
+///
+/// fade.step(Duration::from_millis(100));
+/// assert_eq!(fade.val(), Rgba::new(0.75, 0.75, 0.75, 1.0));
+///
+/// let left = fade.step(Duration::from_millis(150));
+/// assert_eq!(left, Duration::from_millis(50));
+/// assert_eq!(fade.val(), Rgba::WHITE);
+/// assert!(fade.is_finished());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    pub duration: time::Duration,
+    pub ease: Ease,
+
+    elapsed: time::Duration,
+}
+
+impl<T: Interpolate> Tween<T> {
+    pub fn new(from: T, to: T, duration: time::Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            ease: Ease::default(),
+            elapsed: time::Duration::new(0, 0),
+        }
+    }
+
+    /// A tween which holds a value for the given duration.
+    pub fn hold(val: T, duration: time::Duration) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(val.clone(), val, duration)
+    }
+
+    pub fn ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+
+    /// Eased progress of the tween. Usually in the range `0..1`, though some
+    /// curves overshoot.
+    pub fn progress(&self) -> f32 {
+        if self.duration.as_nanos() == 0 {
+            return 1.;
+        }
+        let t = self.elapsed.as_nanos() as f64 / self.duration.as_nanos() as f64;
+        self.ease.apply(t as f32)
+    }
+
+    /// The current value.
+    pub fn val(&self) -> T {
+        self.from.lerp(&self.to, self.progress())
+    }
+}
+
+impl<T> Timeline for Tween<T> {
+    fn duration(&self) -> time::Duration {
+        self.duration
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.elapsed
+    }
+
+    fn seek(&mut self, time: time::Duration) {
+        self.elapsed = time.min(self.duration);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Sequence
+///////////////////////////////////////////////////////////////////////////
+
+/// Timelines played one after the other.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::kit::tween::{Sequence, Timeline, Tween};
+/// use rgx::math::Vector2;
+/// use std::time::Duration;
+///
+/// let ms = Duration::from_millis;
+/// let mut path = Sequence::new()
+///     .then(Tween::new(Vector2::new(0., 0.), Vector2::new(10., 0.), ms(100)))
+///     .then(Tween::hold(Vector2::new(10., 0.), ms(50)))
+///     .then(Tween::new(Vector2::new(10., 0.), Vector2::new(10., 20.), ms(100)));
+///
+/// assert_eq!(path.duration(), ms(250));
+///
+/// path.step(ms(50));
+/// assert_eq!(path.val(), Some(Vector2::new(5., 0.)));
+/// path.step(ms(150));
+/// assert_eq!(path.val(), Some(Vector2::new(10., 10.)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sequence<T> {
+    items: Vec<T>,
+    elapsed: time::Duration,
+}
+
+impl<T: Timeline> Sequence<T> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            elapsed: time::Duration::new(0, 0),
+        }
+    }
+
+    /// Append a timeline to the sequence.
+    pub fn then(mut self, item: T) -> Self {
+        self.push(item);
+        self
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.items.push(item);
+        self.seek(self.elapsed);
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// The timeline currently playing, or the last one if the sequence is
+    /// finished.
+    pub fn current(&self) -> Option<&T> {
+        let mut start = time::Duration::new(0, 0);
+
+        for item in self.items.iter() {
+            start += item.duration();
+            if self.elapsed < start {
+                return Some(item);
+            }
+        }
+        self.items.last()
+    }
+}
+
+impl<V: Interpolate> Sequence<Tween<V>> {
+    /// The current value, if the sequence isn't empty.
+    pub fn val(&self) -> Option<V> {
+        self.current().map(Tween::val)
+    }
+}
+
+impl<T: Timeline> Default for Sequence<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Timeline> Timeline for Sequence<T> {
+    fn duration(&self) -> time::Duration {
+        self.items.iter().map(Timeline::duration).sum()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.elapsed
+    }
+
+    fn seek(&mut self, time: time::Duration) {
+        let mut start = time::Duration::new(0, 0);
+
+        for item in self.items.iter_mut() {
+            item.seek(if time > start {
+                time - start
+            } else {
+                time::Duration::new(0, 0)
+            });
+            start += item.duration();
+        }
+        self.elapsed = time.min(start);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Parallel
+///////////////////////////////////////////////////////////////////////////
+
+/// Two timelines played at the same time. Nest to play more.
+///
+/// # Examples
+///
+/// ```
+/// use rgx::core::{Rect, Rgba};
+/// use rgx::kit::tween::{Parallel, Timeline, Tween};
+/// use std::time::Duration;
+///
+/// let ms = Duration::from_millis;
+/// let mut popup = Parallel(
+///     Tween::new(Rect::new(0., 0., 0., 0.), Rect::new(0., 0., 64., 32.), ms(100)),
+///     Tween::new(Rgba::TRANSPARENT, Rgba::WHITE, ms(200)),
+/// );
+///
+/// assert_eq!(popup.duration(), ms(200));
+///
+/// popup.step(ms(100));
+/// assert_eq!(popup.0.val(), Rect::new(0., 0., 64., 32.));
+/// assert_eq!(popup.1.val(), Rgba::new(0.5, 0.5, 0.5, 0.5));
+/// assert!(!popup.is_finished());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Parallel<A, B>(pub A, pub B);
+
+impl<A: Timeline, B: Timeline> Timeline for Parallel<A, B> {
+    fn duration(&self) -> time::Duration {
+        self.0.duration().max(self.1.duration())
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.0.elapsed().max(self.1.elapsed())
+    }
+
+    fn seek(&mut self, time: time::Duration) {
+        self.0.seek(time);
+        self.1.seek(time);
+    }
+}